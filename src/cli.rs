@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    Parser, Subcommand,
+};
+
+use pm_spotlight::search::search_manager::SearcherName;
+
+// Without subcommand, the GUI is started. The frontend options don't apply to the subcommands.
+//
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Poor Man's Spotlight - a minimal desktop search",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    /// Use the given configuration file, instead of the standard locations
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Prefill the search input
+    #[arg(long, value_name = "TEXT")]
+    pub query: Option<String>,
+
+    /// Send all the patterns to the given searcher, regardless of the prefix
+    #[arg(long, value_name = "NAME", value_parser = searcher_name_parser())]
+    pub searcher: Option<SearcherName>,
+
+    /// Write the selected entry value to stdout, instead of executing it
+    #[arg(long)]
+    pub print: bool,
+
+    /// Run in the terminal, instead of opening a window
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Filter the lines read from stdin, and print the selected one (Shift+Enter: the typed text)
+    #[arg(long, conflicts_with = "searcher")]
+    pub dmenu: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// There is no `index` command, since the file search doesn't use an index (the search paths are walked
+// on each query).
+//
+#[derive(Subcommand)]
+pub enum Command {
+    /// Configuration management
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Stay resident with the window hidden, and show it on the `toggle`/`show` commands
+    #[cfg(target_os = "linux")]
+    Daemon,
+    /// Show the daemon window if hidden, and hide it otherwise
+    #[cfg(target_os = "linux")]
+    Toggle,
+    /// Show the daemon window
+    #[cfg(target_os = "linux")]
+    Show {
+        /// Replace the search input text, and search it
+        #[arg(long, value_name = "TEXT")]
+        query: Option<String>,
+    },
+    /// Hide the daemon window
+    #[cfg(target_os = "linux")]
+    Hide,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Validate the configuration, and report errors and warnings
+    Check,
+}
+
+// The names are validated by the parser, so the conversion can't fail.
+//
+fn searcher_name_parser() -> impl TypedValueParser<Value = SearcherName> {
+    PossibleValuesParser::new(SearcherName::NAMES).map(|name| name.parse().unwrap())
+}
@@ -1,21 +1,530 @@
-use std::fs;
+use std::{
+    env, error, fmt, fs, io,
+    path::{Path, PathBuf},
+};
 
 use serde::Deserialize;
+use toml::{Table, Value};
 
-const CONFIG_BASENAME: &str = ".pm-spotlight";
+#[cfg(target_os = "linux")]
+use crate::search::file_searcher::FileSearcherSettings;
+use crate::{
+    helpers::clipboard_management::ClipboardBackendKind,
+    search::{emoji_searcher::EmojiSearcherSettings, snippet_searcher::SnippetSearcherSettings},
+};
 
-#[derive(Clone, Deserialize)]
+// Layers, from the lowest to the highest precedence:
+//
+// - system: `$XDG_CONFIG_DIRS/pm-spotlight/config.toml` (default: `/etc/xdg`);
+// - legacy: `$HOME/.pm-spotlight`;
+// - user: `$XDG_CONFIG_HOME/pm-spotlight/config.toml` (default: `$HOME/.config`).
+//
+// Missing layers are skipped. Values of higher layers override the lower ones; tables are merged
+// recursively, and arrays are replaced, unless their key is listed in the `append_arrays` directive of
+// the higher layer.
+//
+// Each file can include other files via the `include` directive; included files have lower precedence
+// than the including one. Relative paths are relative to the including file directory.
+//
+const LEGACY_CONFIG_BASENAME: &str = ".pm-spotlight";
+const XDG_CONFIG_DIRNAME: &str = "pm-spotlight";
+const XDG_CONFIG_BASENAME: &str = "config.toml";
+const DEFAULT_XDG_CONFIG_DIRS: &str = "/etc/xdg";
+
+const INCLUDE_DIRECTIVE: &str = "include";
+const APPEND_ARRAYS_DIRECTIVE: &str = "append_arrays";
+
+// WATCH OUT! Keys must not clash with the layer directives.
+//
+/// Configuration, as loaded by [`ConfigManager`]. All the keys are optional; a missing configuration
+/// file is equivalent to an empty one, so `Config::default()` is a valid configuration.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
 pub struct Config {
-    pub search_paths: Vec<String>,
-    pub skip_paths: Vec<String>,
+    /// Paste mode only: restore the previous clipboard content after pasting; applies to all the
+    /// searchers.
+    pub restore_clipboard: bool,
+    /// If not specified, it's detected from the environment.
+    pub clipboard_backend: Option<ClipboardBackendKind>,
+    pub searchers: SearchersConfig,
+    pub preview: PreviewConfig,
+    /// Unknown keys found while loading; they're not errors, so that configurations can be shared
+    /// across versions.
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+/// One section per searcher (`[searchers.<name>]`); each searcher declares its own settings.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchersConfig {
+    pub emoji: EmojiSearcherSettings,
+    pub snippets: SnippetSearcherSettings,
+    #[cfg(target_os = "linux")]
+    pub files: FileSearcherSettings,
+}
+
+/// Preview pane of the GUI (`[preview]`), displaying the selected file.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct PreviewConfig {
+    /// The position is read only on startup.
+    pub position: PreviewPosition,
+    /// Lines displayed for text files, and entries for directories.
+    pub max_lines: usize,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            position: PreviewPosition::Off,
+            max_lines: 100,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PreviewPosition {
+    #[default]
+    Off,
+    Right,
+    Bottom,
+}
+
+// Keys of the versions preceding the searcher sections, and their current location; they're moved
+// before merging, so that layers using different formats are merged correctly.
+//
+const LEGACY_KEYS: &[(&str, &str)] = &[
+    ("search_paths", "searchers.files.search_paths"),
+    ("skip_paths", "searchers.files.skip_paths"),
+];
+
+// Directives are keys of each file, which are processed and removed before merging.
+//
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LayerDirectives {
+    include: Vec<String>,
+    // Dotted key paths, e.g. `searchers.files.skip_paths`.
+    append_arrays: Vec<String>,
+}
+
+struct ConfigLayer {
+    table: Table,
+    append_keys: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Error raised when loading the configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    HomeDirNotFound,
+    Read(PathBuf, io::Error),
+    // Line and column are 1-based.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    IncludeCycle(PathBuf),
+    // Raised after merging the layers, so there is no location.
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HomeDirNotFound => write!(f, "Configuration error: home directory not found"),
+            Self::Read(path, error) => {
+                write!(f, "Configuration error: {}: {}", path.display(), error)
+            }
+            Self::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Configuration error: {}:{}:{}: {}",
+                path.display(),
+                line,
+                column,
+                message.trim()
+            ),
+            Self::IncludeCycle(path) => {
+                write!(f, "Configuration error: {}: include cycle", path.display())
+            }
+            Self::Invalid(message) => write!(f, "Configuration error: {}", message.trim()),
+        }
+    }
 }
 
-pub struct ConfigManager {}
+impl error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Read(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Loads the configuration from its layers (see the README for the locations and the format).
+#[derive(Clone)]
+pub struct ConfigManager {
+    // If specified, it's the only layer (its includes are still processed), and it must exist.
+    config_path: Option<PathBuf>,
+}
 
 impl ConfigManager {
-    pub fn load_configuration() -> Config {
-        let config_filename = dirs::home_dir().unwrap().join(CONFIG_BASENAME); // `dirs` crate
-        let config_str = fs::read_to_string(config_filename).unwrap();
-        toml::from_str(&config_str).unwrap()
+    /// If `config_path` is specified, only that file (and its includes) is loaded, and it must exist;
+    /// otherwise, the standard locations are used.
+    pub fn new(config_path: Option<PathBuf>) -> Self {
+        Self { config_path }
+    }
+
+    /// If no file exists, the default configuration is returned.
+    pub fn load_configuration(&self) -> Result<Config, ConfigError> {
+        let mut merged_table = Table::new();
+        let mut warnings = vec![];
+
+        for path in self.layer_paths()? {
+            if path.is_file() || self.config_path.is_some() {
+                let layer = Self::load_layer(&path, &mut vec![])?;
+                Self::merge_tables(&mut merged_table, layer.table, &layer.append_keys, "");
+                warnings.extend(layer.warnings);
+            }
+        }
+
+        let mut config: Config = Value::Table(merged_table)
+            .try_into()
+            .map_err(|error: toml::de::Error| ConfigError::Invalid(error.message().to_string()))?;
+
+        config.warnings = warnings;
+
+        Ok(config)
+    }
+
+    /// Files that make up the configuration, including the includes, and the layers that don't exist
+    /// (which may be created later). Invalid files are included, but not inspected.
+    pub fn configuration_files(&self) -> Vec<PathBuf> {
+        let mut configuration_files = vec![];
+
+        for path in self.layer_paths().unwrap_or_default() {
+            Self::collect_configuration_files(&path, &mut configuration_files);
+        }
+
+        configuration_files
+    }
+
+    fn collect_configuration_files(path: &Path, configuration_files: &mut Vec<PathBuf>) {
+        // Prevents include cycles.
+        //
+        if configuration_files.iter().any(|file| file == path) {
+            return;
+        }
+
+        configuration_files.push(path.to_path_buf());
+
+        let Ok(config_str) = fs::read_to_string(path) else {
+            return;
+        };
+
+        if let Ok(directives) = toml::from_str::<LayerDirectives>(&config_str) {
+            for include in &directives.include {
+                let include_path = Self::resolve_include_path(include, path);
+                Self::collect_configuration_files(&include_path, configuration_files);
+            }
+        }
+    }
+
+    // Ordered from the lowest to the highest precedence.
+    //
+    fn layer_paths(&self) -> Result<Vec<PathBuf>, ConfigError> {
+        if let Some(config_path) = &self.config_path {
+            return Ok(vec![config_path.clone()]);
+        }
+
+        let home_dir = dirs::home_dir().ok_or(ConfigError::HomeDirNotFound)?; // `dirs` crate
+
+        let xdg_config_dirs =
+            env::var("XDG_CONFIG_DIRS").unwrap_or_else(|_| DEFAULT_XDG_CONFIG_DIRS.to_string());
+
+        // The first XDG dir has the highest precedence.
+        //
+        let mut layer_paths = xdg_config_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .rev()
+            .map(|dir| {
+                Path::new(dir)
+                    .join(XDG_CONFIG_DIRNAME)
+                    .join(XDG_CONFIG_BASENAME)
+            })
+            .collect::<Vec<_>>();
+
+        layer_paths.push(home_dir.join(LEGACY_CONFIG_BASENAME));
+
+        if let Some(config_dir) = dirs::config_dir() {
+            layer_paths.push(
+                config_dir
+                    .join(XDG_CONFIG_DIRNAME)
+                    .join(XDG_CONFIG_BASENAME),
+            );
+        }
+
+        Ok(layer_paths)
+    }
+
+    // Loads the file, merged with its includes. `include_chain` is used to detect cycles.
+    //
+    fn load_layer(
+        path: &Path,
+        include_chain: &mut Vec<PathBuf>,
+    ) -> Result<ConfigLayer, ConfigError> {
+        let canonical_path =
+            fs::canonicalize(path).map_err(|error| ConfigError::Read(path.to_path_buf(), error))?;
+
+        if include_chain.contains(&canonical_path) {
+            return Err(ConfigError::IncludeCycle(path.to_path_buf()));
+        }
+
+        let config_str = fs::read_to_string(path)
+            .map_err(|error| ConfigError::Read(path.to_path_buf(), error))?;
+
+        // Parse the file individually, so that the errors have a location. The directives are parsed
+        // separately, since they're not part of the configuration.
+        //
+        let mut table: Table = Self::parse_str(&config_str, path)?;
+        let mut warnings = Self::validate_str(&config_str, path)?;
+        let directives: LayerDirectives = Self::parse_str(&config_str, path)?;
+
+        table.remove(INCLUDE_DIRECTIVE);
+        table.remove(APPEND_ARRAYS_DIRECTIVE);
+
+        Self::migrate_legacy_keys(&mut table);
+
+        let append_keys = directives
+            .append_arrays
+            .iter()
+            .map(|key| Self::migrate_legacy_key_path(key))
+            .collect::<Vec<_>>();
+
+        include_chain.push(canonical_path);
+
+        let mut layer_table = Table::new();
+        let mut layer_warnings = vec![];
+
+        for include in &directives.include {
+            let include_path = Self::resolve_include_path(include, path);
+            let included_layer = Self::load_layer(&include_path, include_chain)?;
+
+            Self::merge_tables(
+                &mut layer_table,
+                included_layer.table,
+                &included_layer.append_keys,
+                "",
+            );
+            layer_warnings.extend(included_layer.warnings);
+        }
+
+        include_chain.pop();
+
+        Self::merge_tables(&mut layer_table, table, &append_keys, "");
+
+        layer_warnings.append(&mut warnings);
+
+        Ok(ConfigLayer {
+            table: layer_table,
+            append_keys,
+            warnings: layer_warnings,
+        })
+    }
+
+    // Keys of the current format, if present in the same file, take precedence.
+    //
+    fn migrate_legacy_keys(table: &mut Table) {
+        for (legacy_key, key_path) in LEGACY_KEYS {
+            if let Some(value) = table.remove(*legacy_key) {
+                Self::insert_if_missing(table, key_path, value);
+            }
+        }
+    }
+
+    fn migrate_legacy_key_path(key_path: &str) -> String {
+        LEGACY_KEYS
+            .iter()
+            .find(|(legacy_key, _)| *legacy_key == key_path)
+            .map_or(key_path, |(_, key_path)| key_path)
+            .to_string()
+    }
+
+    // Intermediate tables are created if missing; if a non-table value is in the way, the value is
+    // dropped.
+    //
+    fn insert_if_missing(table: &mut Table, key_path: &str, value: Value) {
+        let (parent_keys, key) = key_path.rsplit_once('.').unwrap_or(("", key_path));
+        let mut parent_table = table;
+
+        for parent_key in parent_keys.split('.').filter(|key| !key.is_empty()) {
+            let parent_value = parent_table
+                .entry(parent_key)
+                .or_insert_with(|| Value::Table(Table::new()));
+
+            match parent_value {
+                Value::Table(child_table) => parent_table = child_table,
+                _ => return,
+            }
+        }
+
+        parent_table.entry(key).or_insert(value);
+    }
+
+    fn resolve_include_path(include: &str, including_path: &Path) -> PathBuf {
+        if let Some(home_relative_path) = include.strip_prefix("~/") {
+            if let Some(home_dir) = dirs::home_dir() {
+                return home_dir.join(home_relative_path);
+            }
+        }
+
+        // If the path is absolute, `join()` returns it as is.
+        //
+        including_path.parent().unwrap().join(include)
+    }
+
+    // `key_prefix` is the dotted path of the tables being merged; empty for the root.
+    //
+    fn merge_tables(base: &mut Table, overlay: Table, append_keys: &[String], key_prefix: &str) {
+        for (key, overlay_value) in overlay {
+            let key_path = if key_prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", key_prefix, key)
+            };
+
+            let unmerged_value = match (base.get_mut(&key), overlay_value) {
+                (Some(Value::Table(base_table)), Value::Table(overlay_table)) => {
+                    Self::merge_tables(base_table, overlay_table, append_keys, &key_path);
+                    None
+                }
+                (Some(Value::Array(base_array)), Value::Array(overlay_array))
+                    if append_keys.contains(&key_path) =>
+                {
+                    base_array.extend(overlay_array);
+                    None
+                }
+                (_, overlay_value) => Some(overlay_value),
+            };
+
+            if let Some(overlay_value) = unmerged_value {
+                base.insert(key, overlay_value);
+            }
+        }
+    }
+
+    fn parse_str<T: serde::de::DeserializeOwned>(
+        config_str: &str,
+        path: &Path,
+    ) -> Result<T, ConfigError> {
+        toml::from_str(config_str).map_err(|error| Self::parse_error(config_str, path, error))
+    }
+
+    // Parses the file as configuration, in order to report the errors with their location, and
+    // returns the warnings for the unknown keys (directives and legacy keys excluded).
+    //
+    fn validate_str(config_str: &str, path: &Path) -> Result<Vec<String>, ConfigError> {
+        let mut unknown_keys = vec![];
+
+        let _: Config = serde_ignored::deserialize(toml::Deserializer::new(config_str), |key| {
+            unknown_keys.push(key.to_string())
+        })
+        .map_err(|error| Self::parse_error(config_str, path, error))?;
+
+        let warnings = unknown_keys
+            .into_iter()
+            .filter(|key| {
+                ![INCLUDE_DIRECTIVE, APPEND_ARRAYS_DIRECTIVE]
+                    .into_iter()
+                    .chain(LEGACY_KEYS.iter().map(|(legacy_key, _)| *legacy_key))
+                    .any(|known_key| known_key == key)
+            })
+            .map(|key| {
+                format!(
+                    "Configuration warning: {}: unknown key `{}`",
+                    path.display(),
+                    key
+                )
+            })
+            .collect();
+
+        Ok(warnings)
+    }
+
+    fn parse_error(config_str: &str, path: &Path, error: toml::de::Error) -> ConfigError {
+        let offset = error.span().map(|span| span.start).unwrap_or_default();
+        let (line, column) = Self::line_and_column(config_str, offset);
+
+        ConfigError::Parse {
+            path: path.to_path_buf(),
+            line,
+            column,
+            message: error.message().to_string(),
+        }
+    }
+
+    // Converts a byte offset into (1-based) line and column; the column is counted in chars.
+    //
+    fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+        let preceding_text = &text[..offset.min(text.len())];
+
+        let line = preceding_text.matches('\n').count() + 1;
+        let line_start = preceding_text.rfind('\n').map_or(0, |index| index + 1);
+        let column = preceding_text[line_start..].chars().count() + 1;
+
+        (line, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    fn load_test_configuration(test_name: &str, config_str: &str) -> Config {
+        let path = env::temp_dir().join(format!(
+            "pm-spotlight-test-{}-{test_name}.toml",
+            process::id()
+        ));
+
+        fs::write(&path, config_str).unwrap();
+
+        let config = ConfigManager::new(Some(path.clone())).load_configuration();
+
+        fs::remove_file(path).unwrap();
+
+        config.unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn legacy_keys_are_migrated() {
+        let config = load_test_configuration(
+            "legacy_keys",
+            "search_paths = [\"/legacy\"]\n[searchers.files]\nskip_paths = [\"/current\"]\n",
+        );
+
+        assert_eq!(config.searchers.files.search_paths, vec!["/legacy"]);
+        assert_eq!(config.searchers.files.skip_paths, vec!["/current"]);
+        assert!(config.warnings.is_empty());
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings() {
+        let config = load_test_configuration("unknown_keys", "[output_modes]\nemoji = \"type\"\n");
+
+        assert_eq!(config.warnings.len(), 1);
+        assert!(config.warnings[0].ends_with("unknown key `output_modes`"));
     }
 }
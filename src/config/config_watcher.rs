@@ -0,0 +1,93 @@
+// Watches the configuration files, and notifies the caller on change, so that it can reload the
+// configuration.
+//
+// Directories are watched, rather than files, since editors typically replace the files on save (which
+// invalidates the file watches), and layers may be created after startup. If the directory of a layer
+// doesn't exist, its nearest existing ancestor is watched, and the watches are rearmed when the missing
+// directories are created.
+
+use std::{collections::HashMap, io, path::Path, thread, time::Duration};
+
+use inotify::{Inotify, WatchMask};
+
+use super::config_manager::ConfigManager;
+
+// Editors may perform multiple operations on save; they're coalesced in a single reload.
+//
+const DEBOUNCE_TIME: Duration = Duration::from_millis(200);
+
+const EVENTS_BUFFER_SIZE: usize = 4096;
+
+/// Watches in a separate thread; the callbacks are invoked from that thread. On error, `on_error` is
+/// invoked, and the watching stops.
+pub fn watch_configuration(
+    config_manager: ConfigManager,
+    on_change: impl Fn() + Send + 'static,
+    on_error: impl FnOnce(io::Error) + Send + 'static,
+) {
+    thread::spawn(move || loop {
+        if let Err(error) = wait_for_change(&config_manager) {
+            on_error(error);
+            return;
+        }
+
+        on_change();
+    });
+}
+
+// The configuration files are recomputed on each invocation, since the includes may have changed.
+//
+fn wait_for_change(config_manager: &ConfigManager) -> io::Result<()> {
+    let configuration_files = config_manager.configuration_files();
+
+    let mut inotify = Inotify::init()?;
+    let mut watched_dirs = HashMap::new();
+
+    let watch_mask = WatchMask::CLOSE_WRITE
+        | WatchMask::CREATE
+        | WatchMask::DELETE
+        | WatchMask::MOVED_FROM
+        | WatchMask::MOVED_TO;
+
+    for configuration_file in &configuration_files {
+        let Some(dir) = existing_ancestor(configuration_file) else {
+            continue;
+        };
+
+        let watch_descriptor = inotify.watches().add(dir, watch_mask)?;
+        watched_dirs.insert(watch_descriptor, dir.to_path_buf());
+    }
+
+    let mut buffer = [0; EVENTS_BUFFER_SIZE];
+
+    loop {
+        let mut events = inotify.read_events_blocking(&mut buffer)?;
+
+        // Creating a missing directory of a layer is reported as change as well, so that the watches
+        // are rearmed; the reload is harmless, and picks up a file created in the meantime.
+        //
+        let configuration_changed = events.any(|event| {
+            let changed_path = event
+                .name
+                .zip(watched_dirs.get(&event.wd))
+                .map(|(name, dir)| dir.join(name));
+
+            changed_path.is_some_and(|path| {
+                configuration_files
+                    .iter()
+                    .any(|file| file.starts_with(&path))
+            })
+        });
+
+        if configuration_changed {
+            thread::sleep(DEBOUNCE_TIME);
+            return Ok(());
+        }
+    }
+}
+
+// The parent directory of the file, or, if missing, its nearest existing ancestor.
+//
+fn existing_ancestor(file: &Path) -> Option<&Path> {
+    file.ancestors().skip(1).find(|dir| dir.is_dir())
+}
@@ -0,0 +1,28 @@
+use pm_spotlight::search::search_result_entry::SearchResultEntry;
+
+use crate::gui::preview_pane::Preview;
+
+#[derive(Clone)]
+pub enum MessageEvent {
+    StartSearch(String),
+    UpdateList(Vec<SearchResultEntry>),
+    FocusOnBrowser,
+    // False: normal; true: alternate
+    ExecuteEntry(bool),
+    ReloadConfiguration,
+    // Error raised outside the frontend thread (e.g. by the configuration watcher).
+    DisplayError(String),
+    CycleScope,
+    // Opens the actions of the selected entry.
+    OpenActions,
+    // Performs an action (by id) on the selected entry.
+    ExecuteAction(&'static str),
+    // Recomputes the preview, if the selection changed.
+    UpdatePreview,
+    // Preview id, and the preview computed in the background.
+    DisplayPreview(u32, Box<Preview>),
+    // Daemon mode only. The query, if present, replaces the input text.
+    ShowWindow(Option<String>),
+    HideWindow,
+    ToggleWindow,
+}
@@ -0,0 +1,26 @@
+use super::message_event::MessageEvent::{self, ExecuteEntry, UpdateList};
+use pm_spotlight::search::{result_sink::ResultSink, search_result_entry::SearchResultEntry};
+
+// Channel from the searchers (and the other background components) to the frontend; each frontend
+// implements it for its own channel type.
+//
+// Senders are shared, since they can be moved to separate threads.
+//
+pub trait MessageSender: Send + Sync {
+    fn send(&self, event: MessageEvent);
+}
+
+// Frontends based on messages receive the results as events. The sender is wrapped, since the result
+// sink trait belongs to the library.
+//
+pub struct MessageSink<T>(pub T);
+
+impl<T: MessageSender> ResultSink for MessageSink<T> {
+    fn update_list(&self, entries: Vec<SearchResultEntry>) {
+        self.0.send(UpdateList(entries));
+    }
+
+    fn execute_entry(&self, alternate: bool) {
+        self.0.send(ExecuteEntry(alternate));
+    }
+}
@@ -0,0 +1,22 @@
+// Exit status when the search is canceled; like dmenu, so that scripts can tell a cancel from a
+// selection.
+//
+pub const CANCEL_EXIT_STATUS: i32 = 1;
+
+// Operations performed on the frontends (GUI and TUI) on startup.
+//
+pub trait Frontend {
+    // Sets the input text, without searching.
+    //
+    fn set_query(&mut self, query: &str);
+
+    fn start_search(&mut self);
+
+    // Errors (and warnings) are displayed as (invalid) entries, replacing the current ones.
+    //
+    fn display_errors(&mut self, messages: Vec<String>);
+
+    // Returns only on exit.
+    //
+    fn run(&mut self);
+}
@@ -1,42 +1,107 @@
+use std::{
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
 use fltk::{
-    app::{self, is_event_shift, set_focus, App, Receiver, Sender},
-    browser::HoldBrowser,
-    enums::{CallbackTrigger, Event, Key},
-    group::Pack,
-    image::PngImage,
+    app::{self, is_event_ctrl, is_event_shift, set_focus, App, Receiver, Sender},
+    dialog,
+    enums::{CallbackTrigger, Event, Key, Shortcut},
+    group::{Flex, Pack},
+    image::{PngImage, SharedImage},
     input::Input,
+    menu::{MenuButton, MenuFlag},
     prelude::*,
     window::Window,
 };
 
-use crate::search::{search_manager::SearchManager, search_result_entry::SearchResultEntry};
+use pm_spotlight::{
+    config::config_manager::{ConfigManager, PreviewPosition},
+    search::{
+        action::{Action, OPEN_FOLDER_ACTION_ID},
+        search_manager::SearchManager,
+        search_result_entry::{EntryIcon, SearchResultEntry},
+    },
+};
+#[cfg(target_os = "linux")]
+use pm_spotlight::{
+    config::config_watcher::watch_configuration, search::file_searcher::FileSearcher,
+};
 
-use super::message_event::MessageEvent::{self, *};
+#[cfg(target_os = "linux")]
+use crate::ipc::{ipc_protocol::IpcCommand, ipc_server::IpcServer};
+use crate::{
+    events::{
+        message_event::MessageEvent::{self, *},
+        message_sender::{MessageSender, MessageSink},
+    },
+    frontend::Frontend,
+    gui::{
+        preview_pane::{compute_preview, Preview, PreviewPane},
+        result_browser::ResultBrowser,
+    },
+};
 
 const WINDOW_TITLE: &str = "Poor Man's Spotlight!";
 
 const WINDOW_WIDTH: i32 = 350;
 const WINDOW_HEIGHT: i32 = 500;
+// Width or height, depending on the position; the window is enlarged accordingly.
+const PREVIEW_PANE_SIZE: i32 = 400;
 
 const WINDOW_ICON: &[u8] = include_bytes!("../../resources/window_icon/telescope.png");
 
-const BROWSER_TEXT_SIZE: i32 = 15; // default: 14
+// Seconds; the wait is interrupted by the messages, so it's effectively unlimited.
+//
+const RESIDENT_WAIT_TIME: f64 = 1e6;
 
 pub struct PMSpotlightApp {
     search_manager: SearchManager,
+    config_manager: ConfigManager,
+    // Write the selected entry value to stdout, instead of executing it.
+    print_selection: bool,
+    // Daemon mode: the window is hidden, instead of exiting.
+    resident: bool,
     current_search_id: u32,
     app: App,
     sender: Sender<MessageEvent>,
     receiver: Receiver<MessageEvent>,
-    browser: HoldBrowser,
+    window: Window,
+    browser: ResultBrowser,
     input: Input,
+    // Not displayed; it's used to pop up the actions of the selected entry.
+    actions_menu: MenuButton,
+    // None if disabled.
+    preview_pane: Option<PreviewPane>,
+    // The preview ids work like the search ids; the flag cancels the preview in progress, if any.
+    current_preview_id: u32,
+    previewed_path: Option<PathBuf>,
+    preview_cancelled: Arc<AtomicBool>,
 }
 
 impl PMSpotlightApp {
-    pub fn build(search_manager: SearchManager) -> Self {
+    pub fn build(
+        search_manager: SearchManager,
+        config_manager: ConfigManager,
+        print_selection: bool,
+        resident: bool,
+    ) -> Self {
+        let preview_position = search_manager.config().preview.position;
+
+        let (window_width, window_height) = match preview_position {
+            PreviewPosition::Off => (WINDOW_WIDTH, WINDOW_HEIGHT),
+            PreviewPosition::Right => (WINDOW_WIDTH + PREVIEW_PANE_SIZE, WINDOW_HEIGHT),
+            PreviewPosition::Bottom => (WINDOW_WIDTH, WINDOW_HEIGHT + PREVIEW_PANE_SIZE),
+        };
+
         let app = App::default();
         let mut window = Window::default()
-            .with_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+            .with_size(window_width, window_height)
             .with_label(WINDOW_TITLE);
         let pack = Pack::default().size_of(&window);
 
@@ -45,57 +110,91 @@ impl PMSpotlightApp {
         let (sender, receiver) = app::channel();
 
         let mut input = Input::default().with_size(0, 25);
-        let mut browser = HoldBrowser::default_fill();
+        let actions_menu = MenuButton::default().with_size(0, 0);
+        let mut results_flex = if preview_position == PreviewPosition::Bottom {
+            Flex::default_fill().column()
+        } else {
+            Flex::default_fill().row()
+        };
+        let mut browser = ResultBrowser::build();
+        let preview_pane = (preview_position != PreviewPosition::Off).then(|| {
+            let preview_pane = PreviewPane::build();
+            results_flex.fixed(preview_pane.widget(), PREVIEW_PANE_SIZE);
+            preview_pane
+        });
+        results_flex.end();
 
-        browser.set_text_size(BROWSER_TEXT_SIZE);
         input.set_trigger(CallbackTrigger::Changed);
 
         Self::callback_start_search(&mut input, sender.clone());
         Self::fltk_event_list_execute_entry_and_focus_on_browser(&mut input, sender.clone());
         Self::fltk_event_execute_entry_from_browser(&mut browser, sender.clone());
+        Self::callback_update_preview(&mut browser, sender.clone());
+
+        #[cfg(target_os = "linux")]
+        {
+            let (change_sender, error_sender) = (sender.clone(), sender.clone());
+            watch_configuration(
+                config_manager.clone(),
+                move || change_sender.send(ReloadConfiguration),
+                move |error| {
+                    error_sender.send(DisplayError(format!(
+                        "Configuration watcher error (changes won't be reloaded): {error}"
+                    )))
+                },
+            );
+
+            Self::preload_file_icons(&search_manager);
+        }
 
         pack.end();
         window.make_resizable(true);
         window.end();
-        window.show();
+
+        if !resident {
+            window.show();
+        }
 
         Self {
             search_manager,
+            config_manager,
+            print_selection,
+            resident,
             current_search_id: 0,
             app,
             sender,
             receiver,
+            window,
             browser,
             input,
+            actions_menu,
+            preview_pane,
+            current_preview_id: 0,
+            previewed_path: None,
+            preview_cancelled: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    // Daemon mode: the window is controlled by the commands received from the clients.
+    //
+    #[cfg(target_os = "linux")]
+    pub fn serve_commands(&self, ipc_server: IpcServer) {
+        let sender = self.sender.clone();
+
+        ipc_server.serve(move |command| {
+            sender.send(match command {
+                IpcCommand::Show(query) => ShowWindow(query),
+                IpcCommand::Hide => HideWindow,
+                IpcCommand::Toggle => ToggleWindow,
+            })
+        });
+    }
+
     pub fn set_window_icon(window: &mut Window) {
         let image = PngImage::from_data(WINDOW_ICON).unwrap();
         window.set_icon(Some(image));
     }
 
-    pub fn run(&mut self) {
-        while self.app.wait() {
-            if let Some(event) = self.receiver.recv() {
-                match event {
-                    StartSearch(pattern) => {
-                        self.message_event_start_search(pattern);
-                    }
-                    UpdateList(entries) => {
-                        self.message_event_update_list(entries);
-                    }
-                    FocusOnBrowser => {
-                        self.message_event_focus_on_browser();
-                    }
-                    ExecuteEntry(alternate) => {
-                        self.message_event_execute_entry(alternate);
-                    }
-                }
-            }
-        }
-    }
-
     /***************************************************************************
      * Callbacks
      ***************************************************************************/
@@ -107,6 +206,14 @@ impl PMSpotlightApp {
         });
     }
 
+    // Invoked on selection change.
+    //
+    fn callback_update_preview(browser: &mut ResultBrowser, sender: Sender<MessageEvent>) {
+        browser.set_selection_callback(move || {
+            sender.send(UpdatePreview);
+        });
+    }
+
     /***************************************************************************
      * FLTK event handlers
      ***************************************************************************/
@@ -124,6 +231,15 @@ impl PMSpotlightApp {
             } else if event == Event::KeyDown && app::event_key() == Key::Down {
                 sender.send(FocusOnBrowser);
                 return true;
+            } else if event == Event::KeyDown && app::event_key() == Key::Tab && is_event_ctrl() {
+                sender.send(CycleScope);
+                return true;
+            } else if event == Event::KeyDown && app::event_key() == Key::Tab {
+                sender.send(OpenActions);
+                return true;
+            } else if let Some(action_id) = Self::action_key_binding(event) {
+                sender.send(ExecuteAction(action_id));
+                return true;
             }
 
             false
@@ -131,28 +247,50 @@ impl PMSpotlightApp {
     }
 
     fn fltk_event_execute_entry_from_browser(
-        browser: &mut HoldBrowser,
+        browser: &mut ResultBrowser,
         sender: Sender<MessageEvent>,
     ) {
-        // It seems that Enter-initiated callback is not supported for browsers.
-        //
-        browser.handle(move |_browser, event| {
+        browser.set_event_handler(move |event| {
             if event == Event::KeyDown && app::event_key() == Key::Enter {
                 sender.send(ExecuteEntry(is_event_shift()));
                 return true;
+            } else if event == Event::KeyDown
+                && (app::event_key() == Key::Tab || app::event_key() == Key::Right)
+            {
+                sender.send(OpenActions);
+                return true;
+            } else if let Some(action_id) = Self::action_key_binding(event) {
+                sender.send(ExecuteAction(action_id));
+                return true;
             }
 
             false
         });
     }
 
+    // Actions that can be performed directly, without opening the actions menu.
+    //
+    fn action_key_binding(event: Event) -> Option<&'static str> {
+        if event != Event::KeyDown || !is_event_ctrl() {
+            return None;
+        }
+
+        match app::event_key() {
+            key if key == Key::from_char('o') => Some(OPEN_FOLDER_ACTION_ID),
+            _ => None,
+        }
+    }
+
     /***************************************************************************
      * MessageEvent handlers
      ***************************************************************************/
 
     fn message_event_start_search(&mut self, pattern: String) {
         self.browser.clear();
-        self.current_search_id = self.search_manager.search(pattern, self.sender.clone());
+        self.current_search_id = self
+            .search_manager
+            .search(pattern, Arc::new(MessageSink(self.sender.clone())));
+        self.message_event_update_preview();
     }
 
     fn message_event_update_list(&mut self, entries: Vec<SearchResultEntry>) {
@@ -160,53 +298,430 @@ impl PMSpotlightApp {
             // Can check here or only on the single entry; doesn't matter.
             //
             if self.current_search_id == entry.search_id {
-                // This is wasteful, but the browser wants to own the data. We could keep in #data just
-                // the data strictly needed to perform the execute action, but it's an optimization that
-                // doesn't matter, at least now.
-                //
-                let label = entry.label.clone();
-                let icon = entry.icon.clone();
+                let icon = entry.icon.as_ref().and_then(Self::build_icon);
 
-                self.browser.add_with_data(&label, entry);
-                self.browser.set_icon(self.browser.size(), icon);
+                self.browser.add(entry, icon);
             }
         }
+
+        self.message_event_update_preview();
     }
 
     fn message_event_focus_on_browser(&mut self) {
         if self.browser.size() > 0 {
-            set_focus(&self.browser);
-            self.browser.select(1);
+            self.browser.take_focus();
+
+            // Selecting adds to the existing selection.
+            //
+            if self.browser.selected_indices().is_empty() {
+                self.browser.select(0);
+            }
         }
     }
 
     fn message_event_execute_entry(&mut self, alternate: bool) {
-        let selected_line = if self.browser.value() > 0 {
-            self.browser.value()
-        } else if self.browser.size() > 0 {
-            1
-        } else {
+        let mut entry_values = self
+            .selected_entries()
+            .into_iter()
+            .map(|entry| entry.value.unwrap_or(entry.label))
+            .collect::<Vec<_>>();
+
+        if entry_values.is_empty() {
+            return;
+        }
+
+        if self.print_selection {
+            for entry_value in entry_values {
+                println!("{entry_value}");
+            }
+            process::exit(0);
+        }
+
+        // The single-entry execution is kept distinct, since the batch one may behave differently
+        // (e.g. copying rather than typing). The result is false if the execution is not supported.
+        //
+        let execute_result = match (entry_values.len(), alternate) {
+            (1, false) => self
+                .search_manager
+                .execute(entry_values.remove(0))
+                .map(|_| true),
+            (1, true) => self.search_manager.alt_execute(entry_values.remove(0)),
+            (_, false) => self
+                .search_manager
+                .execute_batch(entry_values)
+                .map(|_| true),
+            (_, true) => self.search_manager.alt_execute_batch(entry_values),
+        };
+
+        match execute_result {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(error) => {
+                self.display_error(error.to_string());
+                return;
+            }
+        }
+
+        self.close_window();
+    }
+
+    // The entry to preview is the one that would be executed; the preview is not recomputed if it's
+    // unchanged.
+    //
+    fn message_event_update_preview(&mut self) {
+        if self.preview_pane.is_none() {
+            return;
+        }
+
+        let path = self
+            .selected_entry()
+            .and_then(|entry| self.search_manager.preview_path(&entry));
+
+        if path == self.previewed_path {
+            return;
+        }
+
+        self.preview_cancelled.store(true, Ordering::Relaxed);
+        self.preview_cancelled = Arc::new(AtomicBool::new(false));
+        self.current_preview_id = self.current_preview_id.wrapping_add(1);
+        self.previewed_path = path.clone();
+
+        let preview_pane = self.preview_pane.as_mut().unwrap();
+
+        preview_pane.clear();
+
+        let Some(path) = path else {
+            return;
+        };
+
+        let thumbnail_size = preview_pane.thumbnail_size();
+        let max_lines = self.search_manager.config().preview.max_lines;
+        let preview_id = self.current_preview_id;
+        let cancelled = self.preview_cancelled.clone();
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            if let Some(preview) = compute_preview(&path, max_lines, thumbnail_size, &cancelled) {
+                sender.send(DisplayPreview(preview_id, Box::new(preview)));
+            }
+        });
+    }
+
+    fn message_event_display_preview(&mut self, preview_id: u32, preview: Preview) {
+        if let Some(preview_pane) = &mut self.preview_pane {
+            if preview_id == self.current_preview_id {
+                preview_pane.display(preview);
+            }
+        }
+    }
+
+    // The menu is modal, so the action is performed directly, rather than via message.
+    //
+    fn message_event_open_actions(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+
+        let actions = self.search_manager.actions(&entry);
+
+        if actions.is_empty() {
+            return;
+        }
+
+        self.actions_menu.clear();
+
+        for action in &actions {
+            self.actions_menu.add(
+                &Self::escape_menu_label(&action.label),
+                Shortcut::None,
+                MenuFlag::Normal,
+                |_| {},
+            );
+        }
+
+        if self.actions_menu.popup().is_none() {
+            return;
+        }
+
+        let Some(action) = usize::try_from(self.actions_menu.value())
+            .ok()
+            .and_then(|index| actions.get(index))
+        else {
+            return;
+        };
+
+        self.execute_action(action, entry.value.unwrap_or(entry.label));
+    }
+
+    // Ignored if the searcher doesn't offer the action for the selected entry.
+    //
+    fn message_event_execute_action(&mut self, action_id: &str) {
+        let Some(entry) = self.selected_entry() else {
             return;
         };
 
-        let entry: SearchResultEntry = unsafe { self.browser.data(selected_line) }.unwrap();
+        let actions = self.search_manager.actions(&entry);
+
+        if let Some(action) = actions.iter().find(|action| action.id == action_id) {
+            self.execute_action(action, entry.value.unwrap_or(entry.label));
+        }
+    }
 
-        if self.current_search_id == entry.search_id && entry.valid {
-            let entry_value = entry.value.unwrap_or(entry.label);
+    // On error, the current configuration is kept.
+    //
+    fn message_event_reload_configuration(&mut self) {
+        match self.config_manager.load_configuration() {
+            Ok(config) => {
+                let warnings = config.warnings.clone();
 
-            if alternate {
-                let alt_executed = self.search_manager.alt_execute(entry_value);
+                self.search_manager.update_config(config);
+                self.update_window_title();
 
-                if !alt_executed {
-                    return;
+                // Refresh the results, which also clears previous errors; warnings are displayed
+                // instead, until the next search.
+                //
+                if warnings.is_empty() {
+                    self.sender.send(StartSearch(self.input.value()));
+                } else {
+                    self.display_errors(warnings);
                 }
-            } else {
-                self.search_manager.execute(entry_value);
             }
+            Err(error) => self.display_error(error.to_string()),
+        }
+    }
 
-            self.input.set_value("");
-            set_focus(&self.input);
-            self.browser.clear();
+    fn message_event_cycle_scope(&mut self) {
+        self.search_manager.cycle_scope();
+        self.update_window_title();
+
+        self.sender.send(StartSearch(self.input.value()));
+    }
+
+    fn message_event_show_window(&mut self, query: Option<String>) {
+        if let Some(query) = query {
+            self.input.set_value(&query);
+            self.sender.send(StartSearch(query));
         }
+
+        self.window.show();
+        set_focus(&self.input);
+    }
+
+    fn message_event_hide_window(&mut self) {
+        self.window.hide();
+    }
+
+    fn message_event_toggle_window(&mut self) {
+        if self.window.shown() {
+            self.message_event_hide_window();
+        } else {
+            self.message_event_show_window(None);
+        }
+    }
+
+    /***************************************************************************
+     * Helpers
+     ***************************************************************************/
+
+    fn display_error(&mut self, message: String) {
+        self.display_errors(vec![message]);
+    }
+
+    // The selected entries, or the first one if none is selected; the non-executable ones are excluded.
+    //
+    fn selected_entries(&self) -> Vec<SearchResultEntry> {
+        let mut selected_indices = self.browser.selected_indices();
+
+        if selected_indices.is_empty() && self.browser.size() > 0 {
+            selected_indices.push(0);
+        }
+
+        selected_indices
+            .into_iter()
+            .filter_map(|index| self.browser.entry(index))
+            .filter(|entry| self.current_search_id == entry.search_id && entry.valid)
+            .collect()
+    }
+
+    // Actions apply to a single entry; when multiple entries are selected, to the first one.
+    //
+    fn selected_entry(&self) -> Option<SearchResultEntry> {
+        self.selected_entries().into_iter().next()
+    }
+
+    fn execute_action(&mut self, action: &Action, entry_value: String) {
+        if let Some(confirmation) = &action.confirmation {
+            if dialog::choice2_default(confirmation, "Cancel", &action.label, "") != Some(1) {
+                return;
+            }
+        }
+
+        let input = match &action.input {
+            Some(action_input) => {
+                match dialog::input_default(&action_input.prompt, &action_input.default_value) {
+                    Some(input) => Some(input),
+                    None => return,
+                }
+            }
+            None => None,
+        };
+
+        if let Err(error) = self
+            .search_manager
+            .execute_action(action, entry_value, input)
+        {
+            self.display_error(error.to_string());
+        } else if action.refresh {
+            self.sender.send(StartSearch(self.input.value()));
+        } else {
+            self.close_window();
+        }
+    }
+
+    // FLTK interprets some chars of the menu labels (`/` separates submenus, `_` adds a divider, `&`
+    // marks a shortcut, and `\` escapes), so they're escaped, in order for the action labels to be
+    // displayed as they are.
+    //
+    fn escape_menu_label(label: &str) -> String {
+        label
+            .chars()
+            .flat_map(|char| match char {
+                '\\' | '/' | '_' => vec!['\\', char],
+                '&' => vec!['&', '&'],
+                _ => vec![char],
+            })
+            .collect()
+    }
+
+    // Invoked after a successful execution. When resident, the window is reset for the next usage.
+    //
+    fn close_window(&mut self) {
+        if !self.resident {
+            process::exit(0);
+        }
+
+        self.window.hide();
+        self.input.set_value("");
+        set_focus(&self.input);
+        self.browser.clear();
+        self.message_event_update_preview();
+    }
+
+    // When resident, the loop keeps running while the window is hidden.
+    //
+    fn wait(&self) -> bool {
+        if self.resident {
+            app::wait_for(RESIDENT_WAIT_TIME).unwrap();
+            true
+        } else {
+            self.app.wait()
+        }
+    }
+
+    // Icons that can't be loaded are not displayed.
+    //
+    fn build_icon(icon: &EntryIcon) -> Option<SharedImage> {
+        match icon {
+            EntryIcon::Png(image_data) => {
+                SharedImage::from_image(PngImage::from_data(image_data).ok()?).ok()
+            }
+            EntryIcon::Path(path) => SharedImage::load(path).ok(),
+        }
+    }
+
+    // Done in the background; otherwise, the first search would block the GUI.
+    //
+    #[cfg(target_os = "linux")]
+    fn preload_file_icons(search_manager: &SearchManager) {
+        let config = search_manager.config().clone();
+
+        thread::spawn(move || FileSearcher::preload_icons(&config));
+    }
+
+    fn update_window_title(&mut self) {
+        if let Some(scope) = self.search_manager.active_scope() {
+            self.window.set_label(&format!("{WINDOW_TITLE} [@{scope}]"));
+        } else {
+            self.window.set_label(WINDOW_TITLE);
+        }
+    }
+}
+
+impl Frontend for PMSpotlightApp {
+    fn set_query(&mut self, query: &str) {
+        self.input.set_value(query);
+    }
+
+    fn start_search(&mut self) {
+        self.sender.send(StartSearch(self.input.value()));
+    }
+
+    fn display_errors(&mut self, messages: Vec<String>) {
+        let entries = messages
+            .into_iter()
+            .map(|message| {
+                SearchResultEntry::new(None, message, None, self.current_search_id, false)
+            })
+            .collect();
+
+        self.browser.clear();
+        self.message_event_update_list(entries);
+    }
+
+    fn run(&mut self) {
+        while self.wait() {
+            if let Some(event) = self.receiver.recv() {
+                match event {
+                    StartSearch(pattern) => {
+                        self.message_event_start_search(pattern);
+                    }
+                    UpdateList(entries) => {
+                        self.message_event_update_list(entries);
+                    }
+                    FocusOnBrowser => {
+                        self.message_event_focus_on_browser();
+                    }
+                    ExecuteEntry(alternate) => {
+                        self.message_event_execute_entry(alternate);
+                    }
+                    ReloadConfiguration => {
+                        self.message_event_reload_configuration();
+                    }
+                    DisplayError(message) => {
+                        self.display_error(message);
+                    }
+                    CycleScope => {
+                        self.message_event_cycle_scope();
+                    }
+                    ShowWindow(query) => {
+                        self.message_event_show_window(query);
+                    }
+                    HideWindow => {
+                        self.message_event_hide_window();
+                    }
+                    ToggleWindow => {
+                        self.message_event_toggle_window();
+                    }
+                    OpenActions => {
+                        self.message_event_open_actions();
+                    }
+                    ExecuteAction(action_id) => {
+                        self.message_event_execute_action(action_id);
+                    }
+                    UpdatePreview => {
+                        self.message_event_update_preview();
+                    }
+                    DisplayPreview(preview_id, preview) => {
+                        self.message_event_display_preview(preview_id, *preview);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl MessageSender for Sender<MessageEvent> {
+    fn send(&self, event: MessageEvent) {
+        Sender::send(self, event);
     }
 }
@@ -0,0 +1,230 @@
+// Pane displaying the preview of the selected file: a thumbnail (for images), the metadata, and the
+// content (for text files and directories).
+//
+// The preview is computed in a separate thread, via `compute_preview()`, then displayed via message.
+
+use std::{
+    io,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use fltk::{
+    enums::ColorDepth,
+    frame::Frame,
+    group::Flex,
+    image::{BmpImage, GifImage, JpegImage, PngImage, RgbImage, SvgImage},
+    prelude::*,
+    text::{TextBuffer, TextDisplay, WrapMode},
+};
+
+use pm_spotlight::helpers::file_preview::{
+    build_preview, FileMetadata, FilePreview, PreviewContent,
+};
+
+const THUMBNAIL_HEIGHT: i32 = 200;
+const TEXT_SIZE: i32 = 13;
+
+const SIZE_UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+#[derive(Clone)]
+pub enum Preview {
+    File(FilePreview, Option<Thumbnail>),
+    Error(String),
+}
+
+// Decoded image, in a format that can be sent across threads.
+//
+#[derive(Clone)]
+pub struct Thumbnail {
+    data: Vec<u8>,
+    width: i32,
+    height: i32,
+    depth: ColorDepth,
+}
+
+pub struct PreviewPane {
+    flex: Flex,
+    image_frame: Frame,
+    text_buffer: TextBuffer,
+}
+
+impl PreviewPane {
+    // The widgets are added to the current group.
+    //
+    pub fn build() -> Self {
+        let mut flex = Flex::default().column();
+
+        let image_frame = Frame::default();
+        let mut text_display = TextDisplay::default();
+        let text_buffer = TextBuffer::default();
+
+        text_display.set_buffer(text_buffer.clone());
+        text_display.set_text_size(TEXT_SIZE);
+        text_display.wrap_mode(WrapMode::AtBounds, 0);
+
+        flex.fixed(&image_frame, 0);
+        flex.end();
+
+        Self {
+            flex,
+            image_frame,
+            text_buffer,
+        }
+    }
+
+    pub fn widget(&self) -> &Flex {
+        &self.flex
+    }
+
+    // Maximum size of the thumbnails.
+    //
+    pub fn thumbnail_size(&self) -> (i32, i32) {
+        (self.flex.w(), THUMBNAIL_HEIGHT)
+    }
+
+    pub fn clear(&mut self) {
+        self.text_buffer.set_text("");
+        self.set_thumbnail(None);
+    }
+
+    pub fn display(&mut self, preview: Preview) {
+        match preview {
+            Preview::File(file_preview, thumbnail) => {
+                self.text_buffer
+                    .set_text(&Self::format_file_preview(&file_preview));
+                self.set_thumbnail(thumbnail);
+            }
+            Preview::Error(message) => {
+                self.text_buffer.set_text(&message);
+                self.set_thumbnail(None);
+            }
+        }
+    }
+
+    fn set_thumbnail(&mut self, thumbnail: Option<Thumbnail>) {
+        let image = thumbnail.and_then(|thumbnail| {
+            RgbImage::new(
+                &thumbnail.data,
+                thumbnail.width,
+                thumbnail.height,
+                thumbnail.depth,
+            )
+            .ok()
+        });
+
+        // The frame is collapsed when there is no image, so that the text uses all the space.
+        //
+        let frame_height = if image.is_some() { THUMBNAIL_HEIGHT } else { 0 };
+
+        self.image_frame.set_image(image);
+        self.flex.fixed(&self.image_frame, frame_height);
+        self.flex.redraw();
+    }
+
+    fn format_file_preview(file_preview: &FilePreview) -> String {
+        let mut text = Self::format_metadata(&file_preview.metadata);
+
+        match &file_preview.content {
+            PreviewContent::Text(content) => {
+                text.push_str("\n\n");
+                text.push_str(content);
+            }
+            PreviewContent::Directory(entries, omitted_count) => {
+                text.push_str("\n\n");
+                text.push_str(&entries.join("\n"));
+
+                if *omitted_count > 0 {
+                    text.push_str(&format!("\n... and {omitted_count} more"));
+                }
+            }
+            PreviewContent::Image | PreviewContent::Other => {}
+        }
+
+        text
+    }
+
+    fn format_metadata(metadata: &FileMetadata) -> String {
+        let mut lines = vec![format!("Size: {}", Self::format_size(metadata.size))];
+
+        if let Some(modified) = &metadata.modified {
+            lines.push(format!("Modified: {modified}"));
+        }
+
+        lines.push(format!("Permissions: {}", metadata.permissions));
+
+        if let Some(mime_type) = &metadata.mime_type {
+            lines.push(format!("Type: {mime_type}"));
+        }
+
+        lines.join("\n")
+    }
+
+    fn format_size(size: u64) -> String {
+        if size < 1024 {
+            return format!("{size} bytes");
+        }
+
+        let exponent = ((size as f64).log(1024.0) as usize).min(SIZE_UNITS.len());
+        let scaled_size = size as f64 / 1024_f64.powi(exponent as i32);
+
+        format!("{scaled_size:.1} {}", SIZE_UNITS[exponent - 1])
+    }
+}
+
+// Invoked off the GUI thread; returns None if cancelled.
+//
+pub fn compute_preview(
+    path: &Path,
+    max_lines: usize,
+    thumbnail_size: (i32, i32),
+    cancelled: &AtomicBool,
+) -> Option<Preview> {
+    let file_preview = match build_preview(path, max_lines, cancelled) {
+        Ok(file_preview) => file_preview,
+        Err(error) if error.kind() == io::ErrorKind::Interrupted => return None,
+        Err(error) => return Some(Preview::Error(format!("{}: {error}", path.display()))),
+    };
+
+    let thumbnail = match (&file_preview.content, &file_preview.metadata.mime_type) {
+        (PreviewContent::Image, Some(mime_type)) => load_thumbnail(path, mime_type, thumbnail_size),
+        _ => None,
+    };
+
+    (!cancelled.load(Ordering::Relaxed)).then_some(Preview::File(file_preview, thumbnail))
+}
+
+// The format-specific loaders are used, rather than `SharedImage`, since the latter uses a global
+// cache, which is not thread-safe. Images are never scaled up.
+//
+fn load_thumbnail(
+    path: &Path,
+    mime_type: &str,
+    (max_width, max_height): (i32, i32),
+) -> Option<Thumbnail> {
+    let image = match mime_type {
+        "image/png" => PngImage::load(path).and_then(|image| image.to_rgb()),
+        "image/jpeg" => JpegImage::load(path).and_then(|image| image.to_rgb()),
+        "image/gif" => GifImage::load(path).and_then(|image| image.to_rgb()),
+        "image/bmp" => BmpImage::load(path).and_then(|image| image.to_rgb()),
+        "image/svg+xml" => SvgImage::load(path).and_then(|image| image.to_rgb()),
+        _ => return None,
+    }
+    .ok()?;
+
+    let scale = (max_width as f64 / image.data_w() as f64)
+        .min(max_height as f64 / image.data_h() as f64)
+        .min(1.0);
+
+    let thumbnail = image.copy_sized(
+        ((image.data_w() as f64 * scale) as i32).max(1),
+        ((image.data_h() as f64 * scale) as i32).max(1),
+    );
+
+    Some(Thumbnail {
+        data: thumbnail.to_rgb_data(),
+        width: thumbnail.data_w(),
+        height: thumbnail.data_h(),
+        depth: thumbnail.depth(),
+    })
+}
@@ -0,0 +1,478 @@
+// Custom-drawn list of the search results, used instead of the FLTK browsers, since their format codes
+// apply to whole lines, so the matching parts of the labels can't be highlighted.
+//
+// Each row displays the icon, the label (bold, with the matching parts underlined) and, under it, the
+// detail (dimmed); all the rows have the same height, whether they have an icon/detail or not.
+//
+// Selection: click selects a single entry, Ctrl+click toggles an entry, and Shift+click extends the
+// selection from the last clicked entry; the arrows, PageUp/PageDown and Home/End move the cursor (and
+// the selection), with Shift extending the selection; Ctrl+A selects all the entries.
+//
+// The labels are drawn via `draw_text()`, which, unlike the browsers (and the boxed `draw_text2()`),
+// doesn't interpret `@` as format/symbol prefix, so they don't need escaping.
+
+use std::{cell::RefCell, rc::Rc};
+
+use fltk::{
+    app::{self, is_event_ctrl, is_event_shift, MouseWheel},
+    draw,
+    enums::{Color, Event, Font, Key},
+    group::Flex,
+    image::SharedImage,
+    prelude::*,
+    valuator::Scrollbar,
+    widget::Widget,
+};
+
+use pm_spotlight::search::search_result_entry::{self, SearchResultEntry};
+
+const TITLE_SIZE: i32 = 15;
+const DETAIL_SIZE: i32 = 12;
+// They fit the text; the font metrics are available only while drawing.
+const TITLE_LINE_HEIGHT: i32 = TITLE_SIZE + 5;
+const DETAIL_LINE_HEIGHT: i32 = DETAIL_SIZE + 5;
+const ROW_PADDING: i32 = 3;
+const ROW_HEIGHT: i32 = TITLE_LINE_HEIGHT + DETAIL_LINE_HEIGHT + 2 * ROW_PADDING;
+const ICON_SIZE: i32 = search_result_entry::ICON_SIZE as i32;
+const ICON_TEXT_SPACING: i32 = 6;
+const MATCH_COLOR: Color = Color::DarkBlue;
+const SCROLLBAR_WIDTH: i32 = 16;
+const PAGE_SIZE: isize = 10;
+
+type EventHandler = Box<dyn FnMut(Event) -> bool>;
+type SelectionCallback = Box<dyn FnMut()>;
+
+#[derive(Default)]
+struct BrowserState {
+    entries: Vec<SearchResultEntry>,
+    icons: Vec<Option<SharedImage>>,
+    selected: Vec<bool>,
+    // Entry moved by the keys, and origin of the Shift selections.
+    cursor: usize,
+    anchor: usize,
+    top_row: usize,
+    event_handler: Option<EventHandler>,
+    selection_callback: Option<SelectionCallback>,
+}
+
+pub struct ResultBrowser {
+    rows: Widget,
+    scrollbar: Scrollbar,
+    state: Rc<RefCell<BrowserState>>,
+}
+
+impl ResultBrowser {
+    // The widgets are added to the current group.
+    //
+    pub fn build() -> Self {
+        let mut flex = Flex::default().row();
+        let mut rows = Widget::default();
+        let mut scrollbar = Scrollbar::default();
+        flex.fixed(&scrollbar, SCROLLBAR_WIDTH);
+        flex.end();
+
+        let state = Rc::new(RefCell::new(BrowserState::default()));
+
+        rows.draw({
+            let state = state.clone();
+            move |rows| Self::draw_rows(rows, &mut state.borrow_mut())
+        });
+
+        rows.handle({
+            let state = state.clone();
+            let mut scrollbar = scrollbar.clone();
+            move |rows, event| Self::handle_event(rows, &mut scrollbar, &state, event)
+        });
+
+        scrollbar.set_callback({
+            let state = state.clone();
+            let mut rows = rows.clone();
+            move |scrollbar| {
+                state.borrow_mut().top_row = scrollbar.value() as usize;
+                rows.redraw();
+            }
+        });
+
+        Self {
+            rows,
+            scrollbar,
+            state,
+        }
+    }
+
+    // Receives the events not handled by the browser (e.g. Enter).
+    //
+    pub fn set_event_handler(&mut self, event_handler: impl FnMut(Event) -> bool + 'static) {
+        self.state.borrow_mut().event_handler = Some(Box::new(event_handler));
+    }
+
+    // Invoked when the user changes the selection; not invoked on programmatic changes.
+    //
+    pub fn set_selection_callback(&mut self, selection_callback: impl FnMut() + 'static) {
+        self.state.borrow_mut().selection_callback = Some(Box::new(selection_callback));
+    }
+
+    pub fn clear(&mut self) {
+        {
+            let mut state = self.state.borrow_mut();
+
+            state.entries.clear();
+            state.icons.clear();
+            state.selected.clear();
+            state.cursor = 0;
+            state.anchor = 0;
+            state.top_row = 0;
+        }
+
+        self.refresh();
+    }
+
+    pub fn add(&mut self, entry: SearchResultEntry, mut icon: Option<SharedImage>) {
+        if let Some(icon) = &mut icon {
+            icon.scale(ICON_SIZE, ICON_SIZE, true, false);
+        }
+
+        {
+            let mut state = self.state.borrow_mut();
+
+            state.entries.push(entry);
+            state.icons.push(icon);
+            state.selected.push(false);
+        }
+
+        self.refresh();
+    }
+
+    pub fn size(&self) -> usize {
+        self.state.borrow().entries.len()
+    }
+
+    pub fn entry(&self, index: usize) -> Option<SearchResultEntry> {
+        self.state.borrow().entries.get(index).cloned()
+    }
+
+    pub fn selected_indices(&self) -> Vec<usize> {
+        let state = self.state.borrow();
+
+        (0..state.entries.len())
+            .filter(|index| state.selected[*index])
+            .collect()
+    }
+
+    // Adds to the selection, and moves the cursor to the entry.
+    //
+    pub fn select(&mut self, index: usize) {
+        {
+            let mut state = self.state.borrow_mut();
+
+            if index >= state.entries.len() {
+                return;
+            }
+
+            state.selected[index] = true;
+            state.cursor = index;
+            state.anchor = index;
+        }
+
+        self.refresh();
+    }
+
+    pub fn take_focus(&mut self) {
+        let _ = self.rows.take_focus();
+    }
+
+    /***************************************************************************
+     * Drawing
+     ***************************************************************************/
+
+    fn draw_rows(rows: &mut Widget, state: &mut BrowserState) {
+        draw::push_clip(rows.x(), rows.y(), rows.w(), rows.h());
+        draw::draw_rect_fill(rows.x(), rows.y(), rows.w(), rows.h(), Color::Background2);
+
+        let visible_rows = (rows.h() / ROW_HEIGHT + 1) as usize;
+        let last_row = (state.top_row + visible_rows).min(state.entries.len());
+
+        for index in state.top_row..last_row {
+            let row_y = rows.y() + (index - state.top_row) as i32 * ROW_HEIGHT;
+
+            Self::draw_row(rows, state, index, row_y);
+        }
+
+        draw::pop_clip();
+    }
+
+    fn draw_row(rows: &Widget, state: &mut BrowserState, index: usize, row_y: i32) {
+        let selected = state.selected[index];
+        let mut text_start_x = rows.x() + ROW_PADDING;
+
+        let (title_color, detail_color) = if selected {
+            draw::draw_rect_fill(rows.x(), row_y, rows.w(), ROW_HEIGHT, Color::Selection);
+            (Color::White, Color::Light2)
+        } else {
+            (Color::Foreground, Color::Dark3)
+        };
+
+        if let Some(icon) = &mut state.icons[index] {
+            let (icon_width, icon_height) = (icon.w(), icon.h());
+
+            icon.draw(
+                text_start_x + (ICON_SIZE - icon_width) / 2,
+                row_y + (ROW_HEIGHT - icon_height) / 2,
+                icon_width,
+                icon_height,
+            );
+            text_start_x += ICON_SIZE + ICON_TEXT_SPACING;
+        }
+
+        let entry = &state.entries[index];
+
+        // Without detail, the title is vertically centered.
+        //
+        let title_y = if entry.detail.is_some() {
+            row_y + ROW_PADDING
+        } else {
+            row_y + (ROW_HEIGHT - TITLE_LINE_HEIGHT) / 2
+        };
+
+        draw::set_font(Font::HelveticaBold, TITLE_SIZE);
+
+        let title_baseline_y = Self::baseline_y(title_y, TITLE_LINE_HEIGHT);
+        let mut text_x = text_start_x;
+
+        // The matches are underlined, and, when the contrast allows it, colored.
+        //
+        for (segment, matching) in entry.label_segments() {
+            let segment_width = draw::width(segment) as i32;
+
+            if matching && !selected {
+                draw::set_draw_color(MATCH_COLOR);
+            } else {
+                draw::set_draw_color(title_color);
+            }
+
+            draw::draw_text(segment, text_x, title_baseline_y);
+
+            if matching {
+                draw::draw_line(
+                    text_x,
+                    title_baseline_y + 1,
+                    text_x + segment_width,
+                    title_baseline_y + 1,
+                );
+            }
+
+            text_x += segment_width;
+        }
+
+        if let Some(detail) = &entry.detail {
+            draw::set_font(Font::Helvetica, DETAIL_SIZE);
+            draw::set_draw_color(detail_color);
+            draw::draw_text(
+                detail,
+                text_start_x,
+                Self::baseline_y(title_y + TITLE_LINE_HEIGHT, DETAIL_LINE_HEIGHT),
+            );
+        }
+
+        if index == state.cursor && rows.has_focus() {
+            draw::draw_rect_with_color(rows.x(), row_y, rows.w(), ROW_HEIGHT, Color::Dark3);
+        }
+    }
+
+    // The font must be set.
+    //
+    fn baseline_y(line_y: i32, line_height: i32) -> i32 {
+        line_y + (line_height + draw::height()) / 2 - draw::descent()
+    }
+
+    /***************************************************************************
+     * Event handling
+     ***************************************************************************/
+
+    fn handle_event(
+        rows: &mut Widget,
+        scrollbar: &mut Scrollbar,
+        state: &Rc<RefCell<BrowserState>>,
+        event: Event,
+    ) -> bool {
+        let selection_changed = match event {
+            Event::Focus | Event::Unfocus => {
+                rows.redraw();
+                return true;
+            }
+            Event::Push => {
+                let _ = rows.take_focus();
+                Self::click(rows, &mut state.borrow_mut())
+            }
+            Event::MouseWheel => {
+                let offset = match app::event_dy() {
+                    MouseWheel::Down => 3,
+                    MouseWheel::Up => -3,
+                    _ => return false,
+                };
+
+                let mut state = state.borrow_mut();
+                let max_top_row = Self::max_top_row(rows, &state);
+                state.top_row = (state.top_row as isize + offset).clamp(0, max_top_row) as usize;
+                false
+            }
+            Event::KeyDown => {
+                let key_result = Self::key_down(rows, &mut state.borrow_mut());
+
+                match key_result {
+                    Some(selection_changed) => selection_changed,
+                    None => return Self::forward_event(state, event),
+                }
+            }
+            _ => return false,
+        };
+
+        Self::sync_scrollbar(rows, scrollbar, &state.borrow());
+        rows.redraw();
+
+        if selection_changed {
+            Self::invoke_selection_callback(state);
+        }
+
+        true
+    }
+
+    // Returns true if the selection changed.
+    //
+    fn click(rows: &Widget, state: &mut BrowserState) -> bool {
+        let row_offset = ((app::event_y() - rows.y()) / ROW_HEIGHT) as usize;
+        let index = state.top_row + row_offset;
+
+        if index >= state.entries.len() {
+            return false;
+        }
+
+        if is_event_ctrl() {
+            state.selected[index] = !state.selected[index];
+            state.anchor = index;
+        } else if is_event_shift() {
+            Self::select_range(state, index);
+        } else {
+            Self::select_only(state, index);
+            state.anchor = index;
+        }
+
+        state.cursor = index;
+
+        true
+    }
+
+    // Returns None if the key is not handled, otherwise, whether the selection changed.
+    //
+    fn key_down(rows: &Widget, state: &mut BrowserState) -> Option<bool> {
+        let key = app::event_key();
+
+        if is_event_ctrl() && key == Key::from_char('a') {
+            state.selected.fill(true);
+            return Some(true);
+        }
+
+        let last_index = state.entries.len() as isize - 1;
+
+        let offset = match key {
+            Key::Up => -1,
+            Key::Down => 1,
+            Key::PageUp => -PAGE_SIZE,
+            Key::PageDown => PAGE_SIZE,
+            Key::Home => -last_index,
+            Key::End => last_index,
+            _ => return None,
+        };
+
+        if state.entries.is_empty() {
+            return Some(false);
+        }
+
+        let index = (state.cursor as isize + offset).clamp(0, last_index) as usize;
+
+        if is_event_shift() {
+            Self::select_range(state, index);
+        } else {
+            Self::select_only(state, index);
+            state.anchor = index;
+        }
+
+        state.cursor = index;
+
+        // Scrolls, if needed, to the cursor.
+        //
+        let visible_rows = (rows.h() / ROW_HEIGHT).max(1) as usize;
+
+        if index < state.top_row {
+            state.top_row = index;
+        } else if index >= state.top_row + visible_rows {
+            state.top_row = index + 1 - visible_rows;
+        }
+
+        Some(true)
+    }
+
+    fn select_only(state: &mut BrowserState, index: usize) {
+        state.selected.fill(false);
+        state.selected[index] = true;
+    }
+
+    // From the anchor to the index.
+    //
+    fn select_range(state: &mut BrowserState, index: usize) {
+        let (start, end) = if index < state.anchor {
+            (index, state.anchor)
+        } else {
+            (state.anchor, index)
+        };
+
+        state.selected.fill(false);
+        state.selected[start..=end].fill(true);
+    }
+
+    // The handler is taken out of the state while invoked, so that it can't cause a double borrow.
+    //
+    fn forward_event(state: &Rc<RefCell<BrowserState>>, event: Event) -> bool {
+        let Some(mut event_handler) = state.borrow_mut().event_handler.take() else {
+            return false;
+        };
+
+        let handled = event_handler(event);
+        state.borrow_mut().event_handler = Some(event_handler);
+
+        handled
+    }
+
+    fn invoke_selection_callback(state: &Rc<RefCell<BrowserState>>) {
+        let Some(mut selection_callback) = state.borrow_mut().selection_callback.take() else {
+            return;
+        };
+
+        selection_callback();
+        state.borrow_mut().selection_callback = Some(selection_callback);
+    }
+
+    /***************************************************************************
+     * Helpers
+     ***************************************************************************/
+
+    fn refresh(&mut self) {
+        Self::sync_scrollbar(&self.rows, &mut self.scrollbar, &self.state.borrow());
+        self.rows.redraw();
+    }
+
+    fn max_top_row(rows: &Widget, state: &BrowserState) -> isize {
+        let visible_rows = (rows.h() / ROW_HEIGHT) as isize;
+
+        (state.entries.len() as isize - visible_rows).max(0)
+    }
+
+    fn sync_scrollbar(rows: &Widget, scrollbar: &mut Scrollbar, state: &BrowserState) {
+        let max_top_row = Self::max_top_row(rows, state);
+        let visible_rows = (rows.h() / ROW_HEIGHT).max(1) as f32;
+
+        scrollbar.set_bounds(0.0, max_top_row as f64);
+        scrollbar.set_value(state.top_row as f64);
+        scrollbar.set_slider_size((visible_rows / state.entries.len().max(1) as f32).min(1.0));
+    }
+}
@@ -4,36 +4,414 @@
 //   effects, like not pasting on the first paste invocation, or the paste being displayed in the
 //   destination program only after other chars were typed.
 // - Clipboard 0.5.0 did not copy emojis on Linux (!)
+//
+// For this reason, on Linux, the clipboard is managed via external programs; the backend is detected
+// from the environment, unless configured.
+
+use std::{error, fmt, io};
+
+use serde::Deserialize;
 
 #[cfg(not(target_os = "linux"))]
 use clipboard::{ClipboardContext, ClipboardProvider};
 
+#[cfg(target_os = "linux")]
+use super::executables::program_in_path;
+#[cfg(target_os = "linux")]
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 #[cfg(target_os = "linux")]
 use std::{
-    io::Write,
+    env,
+    fs::OpenOptions,
+    io::{IsTerminal, Write},
     process::{Command, Stdio},
 };
 
-pub fn copy_to_clipboard(text: String) {
-    #[cfg(not(target_os = "linux"))]
-    {
+/// Means of accessing the clipboard; the external programs are Linux-only.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardBackendKind {
+    Xsel,
+    Xclip,
+    WlCopy,
+    /// Terminal escape sequence; useful when running over SSH, or without a display server.
+    Osc52,
+}
+
+/// Error raised when accessing the clipboard; it's meant to be displayed to the user.
+#[derive(Debug)]
+pub enum ClipboardError {
+    /// The clipboard program is not installed.
+    ProgramNotFound(&'static str),
+    /// The clipboard program exited with an error (second field: status).
+    ProgramFailed(&'static str, String),
+    /// The backend doesn't support the operation (e.g. reading, with OSC 52).
+    Unsupported(&'static str),
+    Io(io::Error),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProgramNotFound(program) => write!(
+                f,
+                "Clipboard program `{program}` not found; install it, or configure another backend"
+            ),
+            Self::ProgramFailed(program, status) => {
+                write!(f, "Clipboard program `{program}` failed ({status})")
+            }
+            Self::Unsupported(operation) => {
+                write!(f, "The clipboard backend doesn't support {operation}")
+            }
+            Self::Io(error) => write!(f, "Clipboard error: {error}"),
+        }
+    }
+}
+
+impl error::Error for ClipboardError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClipboardError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Target selection(s) of a copy. PRIMARY is the X11/Wayland selection pasted via middle click.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardSelection {
+    #[default]
+    Clipboard,
+    Primary,
+    Both,
+}
+
+#[cfg(target_os = "linux")]
+impl ClipboardSelection {
+    fn includes_clipboard(&self) -> bool {
+        matches!(self, Self::Clipboard | Self::Both)
+    }
+
+    fn includes_primary(&self) -> bool {
+        matches!(self, Self::Primary | Self::Both)
+    }
+}
+
+pub(crate) trait ClipboardBackend {
+    // `mime_type` is the target type of the content (e.g. `text/uri-list`); None means plain text.
+    // Backends that don't support target types return `Unsupported`, rather than copying the content
+    // as plain text, since it may not be meaningful as such.
+    //
+    fn copy(
+        &self,
+        text: &str,
+        selection: ClipboardSelection,
+        mime_type: Option<&str>,
+    ) -> Result<(), ClipboardError>;
+
+    // Reads the CLIPBOARD selection.
+    // Returns an empty string if the clipboard is empty, or its content is not text.
+    //
+    fn read(&self) -> Result<String, ClipboardError>;
+
+    // Shell command that copies its stdin to the CLIPBOARD selection, as plain text; required by
+    // processes that outlive pm-spotlight. None if not supported.
+    //
+    fn shell_copy_command(&self) -> Option<String> {
+        None
+    }
+}
+
+pub(crate) fn copy_to_clipboard(
+    text: String,
+    selection: ClipboardSelection,
+    mime_type: Option<&str>,
+    backend_kind: Option<ClipboardBackendKind>,
+) -> Result<(), ClipboardError> {
+    build_backend(backend_kind).copy(&text, selection, mime_type)
+}
+
+pub(crate) fn read_from_clipboard(
+    backend_kind: Option<ClipboardBackendKind>,
+) -> Result<String, ClipboardError> {
+    build_backend(backend_kind).read()
+}
+
+// On platforms other than Linux, the native clipboard is always used.
+//
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn build_backend(
+    _backend_kind: Option<ClipboardBackendKind>,
+) -> Box<dyn ClipboardBackend> {
+    Box::new(NativeBackend {})
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn build_backend(
+    backend_kind: Option<ClipboardBackendKind>,
+) -> Box<dyn ClipboardBackend> {
+    match backend_kind.unwrap_or_else(detect_backend_kind) {
+        ClipboardBackendKind::Xsel => Box::new(CommandBackend {
+            program: ClipboardProgram::Xsel,
+        }),
+        ClipboardBackendKind::Xclip => Box::new(CommandBackend {
+            program: ClipboardProgram::Xclip,
+        }),
+        ClipboardBackendKind::WlCopy => Box::new(CommandBackend {
+            program: ClipboardProgram::WlCopy,
+        }),
+        ClipboardBackendKind::Osc52 => Box::new(Osc52Backend {}),
+    }
+}
+
+// Wayland takes precedence, since X11 programs may work under XWayland, but not reliably.
+//
+#[cfg(target_os = "linux")]
+fn detect_backend_kind() -> ClipboardBackendKind {
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        ClipboardBackendKind::WlCopy
+    } else if env::var_os("DISPLAY").is_some() {
+        if !program_in_path("xsel") && program_in_path("xclip") {
+            ClipboardBackendKind::Xclip
+        } else {
+            ClipboardBackendKind::Xsel
+        }
+    } else {
+        ClipboardBackendKind::Osc52
+    }
+}
+
+// There is no PRIMARY selection on other platforms, so the content is always copied to the clipboard.
+//
+#[cfg(not(target_os = "linux"))]
+struct NativeBackend {}
+
+#[cfg(not(target_os = "linux"))]
+impl ClipboardBackend for NativeBackend {
+    fn copy(
+        &self,
+        text: &str,
+        _selection: ClipboardSelection,
+        mime_type: Option<&str>,
+    ) -> Result<(), ClipboardError> {
+        if mime_type.is_some() {
+            return Err(ClipboardError::Unsupported("target types"));
+        }
+
+        let mut ctx: ClipboardContext =
+            ClipboardProvider::new().map_err(|error| io::Error::other(error.to_string()))?;
+        ctx.set_contents(text.to_string())
+            .map_err(|error| ClipboardError::Io(io::Error::other(error.to_string())))
+    }
+
+    fn read(&self) -> Result<String, ClipboardError> {
         let mut ctx: ClipboardContext =
-            ClipboardProvider::new().expect("Failed to initialize clipboard");
-        ctx.set_contents(text)
-            .expect("Failed to set clipboard contents");
-    }
-    #[cfg(target_os = "linux")]
-    {
-        let mut child = Command::new("xsel")
-            .arg("-ib")
+            ClipboardProvider::new().map_err(|error| io::Error::other(error.to_string()))?;
+        Ok(ctx.get_contents().unwrap_or_default())
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+enum ClipboardProgram {
+    // Doesn't support target types.
+    Xsel,
+    Xclip,
+    WlCopy,
+}
+
+// Backend based on external programs, that read/write the clipboard content from/to stdin/stdout.
+//
+#[cfg(target_os = "linux")]
+struct CommandBackend {
+    program: ClipboardProgram,
+}
+
+#[cfg(target_os = "linux")]
+impl CommandBackend {
+    // Returns the program name, and the arguments.
+    //
+    fn copy_command<'a>(
+        &self,
+        primary: bool,
+        mime_type: Option<&'a str>,
+    ) -> (&'static str, Vec<&'a str>) {
+        match self.program {
+            ClipboardProgram::Xsel => ("xsel", vec!["-i", if primary { "-p" } else { "-b" }]),
+            ClipboardProgram::Xclip => {
+                let mut args = vec![
+                    "-i",
+                    "-selection",
+                    if primary { "primary" } else { "clipboard" },
+                ];
+                if let Some(mime_type) = mime_type {
+                    args.extend(["-t", mime_type]);
+                }
+                ("xclip", args)
+            }
+            ClipboardProgram::WlCopy => {
+                let mut args = vec![];
+                if primary {
+                    args.push("--primary");
+                }
+                if let Some(mime_type) = mime_type {
+                    args.extend(["--type", mime_type]);
+                }
+                ("wl-copy", args)
+            }
+        }
+    }
+
+    fn read_command(&self) -> (&'static str, Vec<&'static str>) {
+        match self.program {
+            ClipboardProgram::Xsel => ("xsel", vec!["-o", "-b"]),
+            ClipboardProgram::Xclip => ("xclip", vec!["-o", "-selection", "clipboard"]),
+            ClipboardProgram::WlCopy => ("wl-paste", vec!["--no-newline"]),
+        }
+    }
+
+    fn copy_to_selection(
+        &self,
+        text: &str,
+        primary: bool,
+        mime_type: Option<&str>,
+    ) -> Result<(), ClipboardError> {
+        let (program, args) = self.copy_command(primary, mime_type);
+
+        let mut child = Command::new(program)
+            .args(args)
             .stdin(Stdio::piped())
+            .stdout(Stdio::null())
             .spawn()
-            .unwrap();
+            .map_err(|error| Self::map_spawn_error(program, error))?;
+
         let mut child_stdin = child.stdin.take().unwrap();
 
-        write!(child_stdin, "{}", text).unwrap();
+        let write_result = write!(child_stdin, "{}", text);
 
         drop(child_stdin);
-        child.wait().unwrap();
+
+        // Don't capture the stderr; programs like xsel fork a process that serves the selection, and
+        // keeps the inherited file descriptors open.
+        //
+        // The child is waited for also on write error (e.g. if it exited early), so that it doesn't
+        // remain a zombie, which would accumulate when running resident.
+        //
+        let status = child.wait()?;
+
+        write_result?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(ClipboardError::ProgramFailed(program, status.to_string()))
+        }
+    }
+
+    fn map_spawn_error(program: &'static str, error: io::Error) -> ClipboardError {
+        if error.kind() == io::ErrorKind::NotFound {
+            ClipboardError::ProgramNotFound(program)
+        } else {
+            ClipboardError::Io(error)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ClipboardBackend for CommandBackend {
+    fn copy(
+        &self,
+        text: &str,
+        selection: ClipboardSelection,
+        mime_type: Option<&str>,
+    ) -> Result<(), ClipboardError> {
+        if mime_type.is_some() && matches!(self.program, ClipboardProgram::Xsel) {
+            return Err(ClipboardError::Unsupported("target types (xsel)"));
+        }
+
+        if selection.includes_clipboard() {
+            self.copy_to_selection(text, false, mime_type)?;
+        }
+        if selection.includes_primary() {
+            self.copy_to_selection(text, true, mime_type)?;
+        }
+
+        Ok(())
+    }
+
+    fn read(&self) -> Result<String, ClipboardError> {
+        let (program, args) = self.read_command();
+
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|error| Self::map_spawn_error(program, error))?;
+
+        // Programs exit with an error when the clipboard is empty, so the status is not checked.
+        //
+        Ok(String::from_utf8(output.stdout).unwrap_or_default())
+    }
+
+    fn shell_copy_command(&self) -> Option<String> {
+        let (program, args) = self.copy_command(false, None);
+
+        Some(
+            [program]
+                .into_iter()
+                .chain(args)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+}
+
+// The sequence is written to the controlling terminal, and interpreted by the terminal emulator (which
+// must support it). Reading is not supported, since it requires a terminal round trip, which many
+// terminals disable. Target types are not supported either.
+//
+#[cfg(target_os = "linux")]
+struct Osc52Backend {}
+
+#[cfg(target_os = "linux")]
+impl ClipboardBackend for Osc52Backend {
+    fn copy(
+        &self,
+        text: &str,
+        selection: ClipboardSelection,
+        mime_type: Option<&str>,
+    ) -> Result<(), ClipboardError> {
+        if mime_type.is_some() {
+            return Err(ClipboardError::Unsupported("target types (OSC 52)"));
+        }
+
+        let selection_param = match selection {
+            ClipboardSelection::Clipboard => "c",
+            ClipboardSelection::Primary => "p",
+            ClipboardSelection::Both => "cp",
+        };
+        let sequence = format!("\x1b]52;{selection_param};{}\x07", BASE64.encode(text));
+
+        if io::stdout().is_terminal() {
+            let mut stdout = io::stdout();
+            stdout.write_all(sequence.as_bytes())?;
+            stdout.flush()?;
+        } else {
+            let mut tty = OpenOptions::new().write(true).open("/dev/tty")?;
+            tty.write_all(sequence.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn read(&self) -> Result<String, ClipboardError> {
+        Err(ClipboardError::Unsupported("reading (OSC 52)"))
     }
 }
@@ -0,0 +1,6 @@
+use std::env;
+
+pub fn program_in_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|path| path.join(program).is_file()))
+}
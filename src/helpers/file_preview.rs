@@ -0,0 +1,245 @@
+// Preview of the files displayed in the results: the first lines of text files, the entries of
+// directories, and the metadata of all the files.
+//
+// The preview is meant to be built off the UI thread, since it reads the filesystem; it can be cancelled
+// via flag.
+
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, Read},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use chrono::{DateTime, Local};
+
+#[cfg(target_os = "linux")]
+use super::mime_types::query_mime_type;
+
+// Enough for the lines displayed; reading the whole file would be wasteful for large files.
+//
+const MAX_TEXT_BYTES: usize = 64 * 1024;
+// Binary files often contain text sections, so a few control chars are tolerated.
+//
+const MAX_CONTROL_CHARS_RATIO: f64 = 0.05;
+const MODIFIED_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// Preview of a file, built by [`build_preview`].
+#[derive(Clone)]
+pub struct FilePreview {
+    pub metadata: FileMetadata,
+    pub content: PreviewContent,
+}
+
+#[derive(Clone)]
+pub struct FileMetadata {
+    pub size: u64,
+    /// Formatted, in local time; None if not supported by the platform.
+    pub modified: Option<String>,
+    /// Unix format (e.g. `rwxr-xr-x`).
+    pub permissions: String,
+    pub mime_type: Option<String>,
+}
+
+#[derive(Clone)]
+pub enum PreviewContent {
+    /// First lines, decoded.
+    Text(String),
+    /// Sorted; directories have a trailing slash. If the listing is truncated, the number of the
+    /// omitted entries is set.
+    Directory(Vec<String>, usize),
+    /// Images are decoded by the frontend, since the decoders depend on the toolkit.
+    Image,
+    /// Only the metadata is displayed.
+    Other,
+}
+
+/// Builds the preview of the file; it reads the filesystem, so it's meant to be called off the UI
+/// thread. `max_lines` limits both the lines of text files and the entries of directories.
+///
+/// Returns an `Interrupted` error if `cancelled` is set while building.
+pub fn build_preview(
+    path: &Path,
+    max_lines: usize,
+    cancelled: &AtomicBool,
+) -> io::Result<FilePreview> {
+    let metadata = fs::metadata(path)?;
+    let mime_type = mime_type(path, &metadata);
+
+    check_cancelled(cancelled)?;
+
+    let content = if metadata.is_dir() {
+        let (entries, omitted_count) = list_directory(path, max_lines, cancelled)?;
+        PreviewContent::Directory(entries, omitted_count)
+    } else if mime_type
+        .as_deref()
+        .is_some_and(|mime_type| mime_type.starts_with("image/"))
+    {
+        PreviewContent::Image
+    } else {
+        match decode_text(&read_head(path)?) {
+            Some(text) => PreviewContent::Text(first_lines(&text, max_lines)),
+            None => PreviewContent::Other,
+        }
+    };
+
+    check_cancelled(cancelled)?;
+
+    Ok(FilePreview {
+        metadata: FileMetadata {
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(|modified| {
+                DateTime::<Local>::from(modified)
+                    .format(MODIFIED_TIME_FORMAT)
+                    .to_string()
+            }),
+            permissions: format_permissions(&metadata),
+            mime_type,
+        },
+        content,
+    })
+}
+
+// Detects BOMs (UTF-8/16), then falls back to UTF-8 and, if invalid, to Latin-1, which can decode any
+// byte sequence. Returns None if the data looks binary.
+//
+fn decode_text(data: &[u8]) -> Option<String> {
+    if let Some(data) = data.strip_prefix(UTF8_BOM) {
+        return Some(String::from_utf8_lossy(data).to_string());
+    } else if let Some(data) = data.strip_prefix(UTF16LE_BOM) {
+        return Some(decode_utf16(data, u16::from_le_bytes));
+    } else if let Some(data) = data.strip_prefix(UTF16BE_BOM) {
+        return Some(decode_utf16(data, u16::from_be_bytes));
+    }
+
+    let control_chars_count = data
+        .iter()
+        .filter(|byte| byte.is_ascii_control() && !b"\t\n\r\x0C\x1B".contains(byte))
+        .count();
+
+    if data.contains(&0) || control_chars_count as f64 > data.len() as f64 * MAX_CONTROL_CHARS_RATIO
+    {
+        return None;
+    }
+
+    match std::str::from_utf8(data) {
+        Ok(text) => Some(text.to_string()),
+        // The data may have been truncated in the middle of a char.
+        //
+        Err(error) if error.error_len().is_none() => {
+            Some(String::from_utf8_lossy(&data[..error.valid_up_to()]).to_string())
+        }
+        Err(_) => Some(data.iter().map(|byte| *byte as char).collect()),
+    }
+}
+
+fn decode_utf16(data: &[u8], to_u16: fn([u8; 2]) -> u16) -> String {
+    let code_units = data
+        .chunks_exact(2)
+        .map(|bytes| to_u16([bytes[0], bytes[1]]));
+
+    char::decode_utf16(code_units)
+        .map(|char| char.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn read_head(path: &Path) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(MAX_TEXT_BYTES);
+
+    File::open(path)?
+        .take(MAX_TEXT_BYTES as u64)
+        .read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+fn first_lines(text: &str, max_lines: usize) -> String {
+    text.lines().take(max_lines).collect::<Vec<_>>().join("\n")
+}
+
+// Returns the entries, and the number of the omitted ones.
+//
+fn list_directory(
+    path: &Path,
+    max_entries: usize,
+    cancelled: &AtomicBool,
+) -> io::Result<(Vec<String>, usize)> {
+    let mut entries = vec![];
+
+    for dir_entry in fs::read_dir(path)? {
+        check_cancelled(cancelled)?;
+
+        let dir_entry = dir_entry?;
+        let mut name = dir_entry.file_name().to_string_lossy().to_string();
+
+        if dir_entry
+            .file_type()
+            .is_ok_and(|file_type| file_type.is_dir())
+        {
+            name.push('/');
+        }
+
+        entries.push(name);
+    }
+
+    entries.sort();
+
+    let omitted_count = entries.len().saturating_sub(max_entries);
+    entries.truncate(max_entries);
+
+    Ok((entries, omitted_count))
+}
+
+fn mime_type(path: &Path, metadata: &Metadata) -> Option<String> {
+    if metadata.is_dir() {
+        return Some("inode/directory".to_string());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        query_mime_type(path)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+#[cfg(unix)]
+fn format_permissions(metadata: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = metadata.permissions().mode();
+
+    (0..9)
+        .map(|bit| {
+            if mode & (0o400 >> bit) == 0 {
+                '-'
+            } else {
+                ['r', 'w', 'x'][bit % 3]
+            }
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn format_permissions(metadata: &Metadata) -> String {
+    if metadata.permissions().readonly() {
+        "read-only".to_string()
+    } else {
+        "read-write".to_string()
+    }
+}
+
+fn check_cancelled(cancelled: &AtomicBool) -> io::Result<()> {
+    if cancelled.load(Ordering::Relaxed) {
+        Err(io::ErrorKind::Interrupted.into())
+    } else {
+        Ok(())
+    }
+}
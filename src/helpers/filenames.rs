@@ -1,5 +1,10 @@
 use std::{collections::HashMap, path::Path};
 
+// Bytes that don't need to be percent-encoded in a file URI path (RFC 3986 unreserved chars, plus the
+// separator).
+//
+const URI_PATH_SAFE_BYTES: &[u8] = b"-._~/";
+
 // Map full filenames to unique short names, adding parents where required:
 //
 //   [a/b/c/d, a/b/d/d, e/f] -> {c/d => a/b/c/d, d/d => a/b/d/d, f => e/f}
@@ -50,3 +55,25 @@ pub fn map_filenames_to_short_names(filenames: Vec<String>) -> HashMap<String, S
 
     result
 }
+
+// Converts an absolute path to a `file://` URI, as used in `text/uri-list`.
+//
+pub fn path_to_file_uri(path: &Path) -> String {
+    format!("file://{}", percent_encode_path(path))
+}
+
+// The raw bytes are encoded, so that paths that are not valid UTF-8 are preserved.
+//
+pub fn percent_encode_path(path: &Path) -> String {
+    path.as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_alphanumeric() || URI_PATH_SAFE_BYTES.contains(&byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,301 @@
+// Icon lookup, according to the freedesktop.org icon theme specification: icons are searched in the
+// theme, then in the themes it inherits from, then in `hicolor` (the fallback of all the themes), and
+// finally among the unthemed icons.
+//
+// Only PNG and SVG icons are looked up, since they're the formats supported by the frontends; scaled
+// directories (for HiDPI) are ignored.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use super::xdg_data_dirs::xdg_data_dirs;
+
+const ICONS_DIRNAME: &str = "icons";
+const HOME_ICONS_DIRNAME: &str = ".icons";
+const PIXMAPS_DIR: &str = "/usr/share/pixmaps";
+const INDEX_BASENAME: &str = "index.theme";
+const INDEX_MAIN_SECTION: &str = "Icon Theme";
+const FALLBACK_THEME: &str = "hicolor";
+const ICON_EXTENSIONS: &[&str] = &["png", "svg"];
+const DEFAULT_THRESHOLD: u32 = 2;
+
+// Shared by the searchers and the frontends, so that the index and the cache are reused (searchers are
+// instantiated on each search); it's reloaded if a different theme or size is requested.
+//
+static SHARED_ICON_THEME: Mutex<Option<IconTheme>> = Mutex::new(None);
+
+pub struct IconTheme {
+    name: String,
+    size: u32,
+    // The theme, followed by the inherited ones; themes not installed are omitted.
+    theme_indexes: Vec<ThemeIndex>,
+    // The lookup stats many files, so the results (including the failed ones) are cached.
+    cache: HashMap<Vec<String>, Option<PathBuf>>,
+}
+
+struct ThemeIndex {
+    // A theme can be installed in multiple base directories (e.g. system and user ones).
+    theme_dirs: Vec<PathBuf>,
+    directories: Vec<ThemeDirectory>,
+}
+
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+    kind: DirectoryKind,
+}
+
+enum DirectoryKind {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+// Looks up the icons via the shared theme instance; see `IconTheme::lookup()`.
+//
+pub fn lookup_icon(theme_name: &str, size: u32, icon_names: &[String]) -> Option<PathBuf> {
+    let mut shared_icon_theme = SHARED_ICON_THEME.lock().unwrap();
+
+    let icon_theme = match &mut *shared_icon_theme {
+        Some(icon_theme) if icon_theme.name == theme_name && icon_theme.size == size => icon_theme,
+        shared_icon_theme => shared_icon_theme.insert(IconTheme::load(theme_name, size)),
+    };
+
+    icon_theme.lookup(icon_names)
+}
+
+impl IconTheme {
+    // `size` is the preferred size of the icons; the closest available is used.
+    //
+    pub fn load(name: &str, size: u32) -> Self {
+        let base_dirs = Self::base_dirs();
+
+        let mut theme_names = vec![name.to_string()];
+        let mut theme_indexes = vec![];
+        let mut index = 0;
+
+        // Inherited themes are appended, so that they're searched breadth-first; the fallback theme is
+        // searched last, even if not inherited explicitly.
+        //
+        while let Some(theme_name) = theme_names.get(index).cloned() {
+            index += 1;
+
+            if let Some((theme_index, parents)) = ThemeIndex::load(&theme_name, &base_dirs) {
+                theme_indexes.push(theme_index);
+
+                for parent in parents {
+                    if !theme_names.contains(&parent) && parent != FALLBACK_THEME {
+                        theme_names.push(parent);
+                    }
+                }
+            }
+
+            if index == theme_names.len() && theme_name != FALLBACK_THEME {
+                theme_names.push(FALLBACK_THEME.to_string());
+            }
+        }
+
+        Self {
+            name: name.to_string(),
+            size,
+            theme_indexes,
+            cache: HashMap::new(),
+        }
+    }
+
+    // Returns the first of the icon names found; the names are ordered by preference (e.g. specific,
+    // then generic).
+    //
+    pub fn lookup(&mut self, icon_names: &[String]) -> Option<PathBuf> {
+        if let Some(icon_path) = self.cache.get(icon_names) {
+            return icon_path.clone();
+        }
+
+        let icon_path = self
+            .theme_indexes
+            .iter()
+            .find_map(|theme_index| {
+                icon_names
+                    .iter()
+                    .find_map(|icon_name| theme_index.lookup(icon_name, self.size))
+            })
+            .or_else(|| {
+                icon_names
+                    .iter()
+                    .find_map(|icon_name| find_icon_file(Path::new(PIXMAPS_DIR), icon_name))
+            });
+
+        self.cache.insert(icon_names.to_vec(), icon_path.clone());
+
+        icon_path
+    }
+
+    // Ordered by precedence.
+    //
+    fn base_dirs() -> Vec<PathBuf> {
+        dirs::home_dir()
+            .map(|home_dir| home_dir.join(HOME_ICONS_DIRNAME))
+            .into_iter()
+            .chain(
+                xdg_data_dirs()
+                    .into_iter()
+                    .map(|data_dir| data_dir.join(ICONS_DIRNAME)),
+            )
+            .collect()
+    }
+}
+
+impl ThemeIndex {
+    // Returns the index, and the names of the inherited themes; None if the theme is not installed.
+    //
+    fn load(theme_name: &str, base_dirs: &[PathBuf]) -> Option<(Self, Vec<String>)> {
+        let theme_dirs = base_dirs
+            .iter()
+            .map(|base_dir| base_dir.join(theme_name))
+            .filter(|theme_dir| theme_dir.is_dir())
+            .collect::<Vec<_>>();
+
+        // The index of the highest precedence directory is used.
+        //
+        let index_sections = theme_dirs.iter().find_map(|theme_dir| {
+            let index = fs::read_to_string(theme_dir.join(INDEX_BASENAME)).ok()?;
+            Some(parse_index(&index))
+        })?;
+
+        let main_section = index_sections.get(INDEX_MAIN_SECTION)?;
+        let list = |key: &str| {
+            main_section
+                .get(key)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        let directories = list("Directories")
+            .into_iter()
+            .filter_map(|path| {
+                let section = index_sections.get(&path)?;
+                let number =
+                    |key: &str| section.get(key).and_then(|value| value.parse::<u32>().ok());
+
+                if number("Scale").unwrap_or(1) != 1 {
+                    return None;
+                }
+
+                let size = number("Size")?;
+                let kind = match section.get("Type").map(String::as_str) {
+                    Some("Fixed") => DirectoryKind::Fixed,
+                    Some("Scalable") => DirectoryKind::Scalable,
+                    _ => DirectoryKind::Threshold,
+                };
+
+                Some(ThemeDirectory {
+                    size,
+                    min_size: number("MinSize").unwrap_or(size),
+                    max_size: number("MaxSize").unwrap_or(size),
+                    threshold: number("Threshold").unwrap_or(DEFAULT_THRESHOLD),
+                    kind,
+                    path,
+                })
+            })
+            .collect();
+
+        Some((
+            Self {
+                theme_dirs,
+                directories,
+            },
+            list("Inherits"),
+        ))
+    }
+
+    // An icon of the exact size is preferred; otherwise, the closest one.
+    //
+    fn lookup(&self, icon_name: &str, size: u32) -> Option<PathBuf> {
+        let mut closest_icon: Option<(u32, PathBuf)> = None;
+
+        for directory in &self.directories {
+            for theme_dir in &self.theme_dirs {
+                let Some(icon_path) = find_icon_file(&theme_dir.join(&directory.path), icon_name)
+                else {
+                    continue;
+                };
+
+                let distance = directory.size_distance(size);
+
+                if distance == 0 {
+                    return Some(icon_path);
+                } else if closest_icon
+                    .as_ref()
+                    .is_none_or(|(closest_distance, _)| distance < *closest_distance)
+                {
+                    closest_icon = Some((distance, icon_path));
+                }
+            }
+        }
+
+        closest_icon.map(|(_, icon_path)| icon_path)
+    }
+}
+
+impl ThemeDirectory {
+    // 0 if the directory provides the size.
+    //
+    fn size_distance(&self, size: u32) -> u32 {
+        let (min_size, max_size) = match self.kind {
+            DirectoryKind::Fixed => (self.size, self.size),
+            DirectoryKind::Scalable => (self.min_size, self.max_size),
+            DirectoryKind::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+
+        min_size.saturating_sub(size) + size.saturating_sub(max_size)
+    }
+}
+
+fn find_icon_file(dir: &Path, icon_name: &str) -> Option<PathBuf> {
+    ICON_EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(format!("{icon_name}.{extension}")))
+        .find(|icon_path| icon_path.is_file())
+}
+
+// Minimal INI parsing (section -> key -> value); comments and invalid lines are ignored.
+//
+fn parse_index(index: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections = HashMap::<String, HashMap<String, String>>::new();
+    let mut current_section_name = None;
+
+    for line in index.lines().map(str::trim) {
+        if let Some(section_name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            current_section_name = Some(section_name.to_string());
+        } else if let (Some(section_name), Some((key, value))) =
+            (&current_section_name, line.split_once('='))
+        {
+            sections
+                .entry(section_name.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    sections
+}
@@ -0,0 +1,689 @@
+// MIME types of the files, and their icon names, according to the freedesktop.org shared-mime-info
+// specification; the database (`$XDG_DATA_DIRS/mime`) is the one generated by `update-mime-database`.
+//
+// The type is determined from the filename (globs) and, if no glob matches, from the content (magic
+// rules). Conflicting globs are resolved by weight and pattern length, without checking the content.
+//
+// For bulk usage (e.g. the icons of the search results), `guess_mime_type()` uses only the filename.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Read,
+    path::Path,
+    sync::OnceLock,
+};
+
+use regex::Regex;
+
+use super::xdg_data_dirs::xdg_data_dirs;
+
+const MIME_DIRNAME: &str = "mime";
+const GLOBS_BASENAME: &str = "globs2";
+const MAGIC_BASENAME: &str = "magic";
+const ICONS_BASENAME: &str = "icons";
+const GENERIC_ICONS_BASENAME: &str = "generic-icons";
+
+const MAGIC_HEADER: &[u8] = b"MIME-Magic\0\n";
+// A few rules look far into the files; reading them entirely would be wasteful.
+const MAX_MAGIC_BYTES: usize = 64 * 1024;
+// Marks the globs of the lower precedence directories as deleted; not supported, since it's rare.
+const NO_GLOBS_MARKER: &str = "__NOGLOBS__";
+const CASE_SENSITIVE_FLAG: &str = "cs";
+
+const DIRECTORY_MIME_TYPE: &str = "inode/directory";
+const EMPTY_FILE_MIME_TYPE: &str = "application/x-zerosize";
+const TEXT_MIME_TYPE: &str = "text/plain";
+const BINARY_MIME_TYPE: &str = "application/octet-stream";
+
+// Loaded on first use, since parsing them takes a while; changes require a restart. The magic rules are
+// loaded separately, since the filename is often enough.
+//
+static MIME_DATABASE: OnceLock<MimeDatabase> = OnceLock::new();
+static MAGIC_DATABASE: OnceLock<MagicDatabase> = OnceLock::new();
+
+struct GlobRule {
+    weight: u32,
+    mime_type: String,
+    pattern: String,
+    case_sensitive: bool,
+}
+
+struct MagicSection {
+    priority: u32,
+    mime_type: String,
+    // In file order; each rule is followed by its children (rules with a higher indent).
+    rules: Vec<MagicRule>,
+}
+
+struct MagicRule {
+    indent: usize,
+    start_offset: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    // Number of the offsets (starting from `start_offset`) where the value is searched.
+    range_length: usize,
+}
+
+#[derive(Default)]
+struct MimeDatabase {
+    // The common patterns (literal filenames, and `*.<extension>`) are indexed by their (lowercase)
+    // text, so that they don't need to be matched one by one.
+    literal_globs: HashMap<String, Vec<GlobRule>>,
+    extension_globs: HashMap<String, Vec<GlobRule>>,
+    other_globs: Vec<(Regex, GlobRule)>,
+    icons: HashMap<String, String>,
+    generic_icons: HashMap<String, String>,
+}
+
+struct MagicDatabase {
+    // Sorted by descending priority.
+    sections: Vec<MagicSection>,
+    // Bytes required by the rules, capped at MAX_MAGIC_BYTES.
+    extent: usize,
+}
+
+// None if the file can't be accessed.
+//
+pub fn query_mime_type(path: &Path) -> Option<String> {
+    MIME_DATABASE
+        .get_or_init(MimeDatabase::load)
+        .mime_type(path)
+}
+
+// Doesn't read the content, so it's cheap; None if no glob matches the filename. Directories are
+// detected via the metadata.
+//
+pub fn guess_mime_type(path: &Path) -> Option<String> {
+    if path.is_dir() {
+        return Some(DIRECTORY_MIME_TYPE.to_string());
+    }
+
+    let filename = path.file_name()?.to_string_lossy();
+
+    MIME_DATABASE
+        .get_or_init(MimeDatabase::load)
+        .glob_mime_type(&filename)
+        .map(str::to_string)
+}
+
+// Candidate icon names of the type, from the most to the least specific (e.g. `text-x-python`, then
+// `text-x-script`, then `text-x-generic`).
+//
+pub fn mime_type_icon_names(mime_type: &str) -> Vec<String> {
+    let database = MIME_DATABASE.get_or_init(MimeDatabase::load);
+    let media = mime_type.split('/').next().unwrap_or_default();
+
+    database
+        .icons
+        .get(mime_type)
+        .cloned()
+        .into_iter()
+        .chain([mime_type.replace('/', "-")])
+        .chain(database.generic_icons.get(mime_type).cloned())
+        .chain([format!("{media}-x-generic")])
+        .collect()
+}
+
+impl MimeDatabase {
+    // Missing or invalid files are ignored, so that an incomplete database still provides the types it
+    // can.
+    //
+    fn load() -> Self {
+        let mut database = Self::default();
+
+        // Loaded from the lowest precedence, so that the icons of the higher ones override them.
+        //
+        for data_dir in xdg_data_dirs().iter().rev() {
+            let mime_dir = data_dir.join(MIME_DIRNAME);
+
+            if let Ok(globs) = fs::read_to_string(mime_dir.join(GLOBS_BASENAME)) {
+                database.add_globs(&globs);
+            }
+
+            for (basename, icons) in [
+                (ICONS_BASENAME, &mut database.icons),
+                (GENERIC_ICONS_BASENAME, &mut database.generic_icons),
+            ] {
+                if let Ok(icons_list) = fs::read_to_string(mime_dir.join(basename)) {
+                    icons.extend(icons_list.lines().filter_map(|line| {
+                        let (mime_type, icon_name) = line.split_once(':')?;
+                        Some((mime_type.to_string(), icon_name.to_string()))
+                    }));
+                }
+            }
+        }
+
+        database
+    }
+
+    // Format: `<weight>:<MIME type>:<glob>[:<flags>]`.
+    //
+    fn add_globs(&mut self, globs: &str) {
+        for line in globs.lines().filter(|line| !line.starts_with('#')) {
+            let mut fields = line.splitn(4, ':');
+
+            let (Some(weight), Some(mime_type), Some(pattern)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            let Ok(weight) = weight.parse() else {
+                continue;
+            };
+
+            if pattern == NO_GLOBS_MARKER {
+                continue;
+            }
+
+            let rule = GlobRule {
+                weight,
+                mime_type: mime_type.to_string(),
+                pattern: pattern.to_string(),
+                case_sensitive: fields
+                    .next()
+                    .is_some_and(|flags| flags.split(',').any(|flag| flag == CASE_SENSITIVE_FLAG)),
+            };
+
+            let is_wildcard = |char| matches!(char, '*' | '?' | '[');
+
+            if !pattern.contains(is_wildcard) {
+                self.literal_globs
+                    .entry(pattern.to_lowercase())
+                    .or_default()
+                    .push(rule);
+            } else if let Some(extension) = pattern
+                .strip_prefix("*.")
+                .filter(|extension| !extension.contains(is_wildcard))
+            {
+                self.extension_globs
+                    .entry(extension.to_lowercase())
+                    .or_default()
+                    .push(rule);
+            } else if let Some(re_glob) = Self::glob_to_regex(pattern, rule.case_sensitive) {
+                self.other_globs.push((re_glob, rule));
+            }
+        }
+    }
+
+    fn glob_to_regex(pattern: &str, case_sensitive: bool) -> Option<Regex> {
+        let mut re_pattern = String::from(if case_sensitive { "^" } else { "(?i)^" });
+        let mut chars = pattern.chars();
+
+        while let Some(char) = chars.next() {
+            match char {
+                '*' => re_pattern.push_str(".*"),
+                '?' => re_pattern.push('.'),
+                '[' => {
+                    let class = chars.by_ref().take_while(|char| *char != ']');
+                    let class = class.collect::<String>();
+
+                    // Only a leading `!` negates; the other chars are literal, except for the ranges.
+                    //
+                    let (negation, class) = match class.strip_prefix('!') {
+                        Some(class) => ("^", class),
+                        None => ("", class.as_str()),
+                    };
+
+                    re_pattern.push('[');
+                    re_pattern.push_str(negation);
+
+                    for char in class.chars() {
+                        match char {
+                            '-' => re_pattern.push('-'),
+                            _ => re_pattern.push_str(&regex::escape(&char.to_string())),
+                        }
+                    }
+
+                    re_pattern.push(']');
+                }
+                _ => re_pattern.push_str(&regex::escape(&char.to_string())),
+            }
+        }
+
+        re_pattern.push('$');
+
+        Regex::new(&re_pattern).ok()
+    }
+
+    fn mime_type(&self, path: &Path) -> Option<String> {
+        let metadata = fs::metadata(path).ok()?;
+
+        if metadata.is_dir() {
+            return Some(DIRECTORY_MIME_TYPE.to_string());
+        }
+
+        if let Some(mime_type) = path
+            .file_name()
+            .and_then(|filename| self.glob_mime_type(&filename.to_string_lossy()))
+        {
+            return Some(mime_type.to_string());
+        }
+
+        if metadata.len() == 0 {
+            return Some(EMPTY_FILE_MIME_TYPE.to_string());
+        }
+
+        let magic_database = MAGIC_DATABASE.get_or_init(MagicDatabase::load);
+        let mut data = vec![];
+
+        File::open(path)
+            .ok()?
+            .take(magic_database.extent.max(1) as u64)
+            .read_to_end(&mut data)
+            .ok()?;
+
+        // Like the other implementations, data without NUL bytes is considered text.
+        //
+        let mime_type = match magic_database.mime_type(&data) {
+            Some(mime_type) => mime_type,
+            None if data.contains(&0) => BINARY_MIME_TYPE,
+            None => TEXT_MIME_TYPE,
+        };
+
+        Some(mime_type.to_string())
+    }
+
+    // The highest weight wins; on ties, the longest pattern, which is the most specific, then the
+    // case-sensitive one (e.g. `*.C` is C++, while `*.c` is C).
+    //
+    fn glob_mime_type(&self, filename: &str) -> Option<&str> {
+        let lowercase_filename = filename.to_lowercase();
+
+        // All the extensions are candidates (e.g. `tar.gz` and `gz`).
+        //
+        let extension_rules = filename
+            .match_indices('.')
+            .filter_map(|(index, _)| {
+                let rules = self
+                    .extension_globs
+                    .get(&filename[index + 1..].to_lowercase())?;
+                Some((&filename[index..], rules))
+            })
+            .flat_map(|(extension, rules)| {
+                rules.iter().filter(move |rule| {
+                    !rule.case_sensitive || rule.pattern.strip_prefix('*') == Some(extension)
+                })
+            });
+
+        let literal_rules = self
+            .literal_globs
+            .get(&lowercase_filename)
+            .into_iter()
+            .flatten()
+            .filter(|rule| !rule.case_sensitive || rule.pattern == filename);
+
+        let other_rules = self
+            .other_globs
+            .iter()
+            .filter(|(re_glob, _)| re_glob.is_match(filename))
+            .map(|(_, rule)| rule);
+
+        literal_rules
+            .chain(extension_rules)
+            .chain(other_rules)
+            .max_by_key(|rule| (rule.weight, rule.pattern.len(), rule.case_sensitive))
+            .map(|rule| rule.mime_type.as_str())
+    }
+}
+
+impl MagicDatabase {
+    // Like the MIME database, missing or invalid files are ignored.
+    //
+    fn load() -> Self {
+        let sections = xdg_data_dirs()
+            .iter()
+            .rev()
+            .filter_map(|data_dir| fs::read(data_dir.join(MIME_DIRNAME).join(MAGIC_BASENAME)).ok())
+            .flat_map(|magic| Self::parse(&magic))
+            .collect::<Vec<_>>();
+
+        Self::new(sections)
+    }
+
+    fn new(mut sections: Vec<MagicSection>) -> Self {
+        // The sort is stable, so the file order is kept for equal priorities.
+        //
+        sections.sort_by_key(|section| u32::MAX - section.priority);
+
+        let extent = sections
+            .iter()
+            .flat_map(|section| &section.rules)
+            .map(|rule| rule.start_offset + rule.range_length + rule.value.len())
+            .max()
+            .unwrap_or_default()
+            .min(MAX_MAGIC_BYTES);
+
+        Self { sections, extent }
+    }
+
+    // Format: a header, followed by sections, each one starting with `[<priority>:<MIME type>]\n`, and
+    // followed by the rules, each one in the format
+    // `[<indent>]><start offset>=<value length (2 bytes, big endian)><value>[&<mask>][~<word size>][+<range length>]\n`.
+    //
+    // On error, the sections parsed so far are returned.
+    //
+    fn parse(mut data: &[u8]) -> Vec<MagicSection> {
+        let mut sections = vec![];
+
+        if !data.starts_with(MAGIC_HEADER) {
+            return sections;
+        }
+
+        data = &data[MAGIC_HEADER.len()..];
+
+        while let Some(first_byte) = data.first() {
+            if *first_byte == b'[' {
+                let Some(section) = Self::parse_section_header(&mut data) else {
+                    break;
+                };
+                sections.push(section);
+            } else {
+                let (Some(section), Some(rule)) =
+                    (sections.last_mut(), Self::parse_rule(&mut data))
+                else {
+                    break;
+                };
+                section.rules.push(rule);
+            }
+        }
+
+        sections
+    }
+
+    fn parse_section_header(data: &mut &[u8]) -> Option<MagicSection> {
+        *data = &data[1..];
+
+        let header = String::from_utf8(take_until(data, b']')?.to_vec()).ok()?;
+        let (priority, mime_type) = header.split_once(':')?;
+
+        skip_byte(data, b'\n')?;
+
+        Some(MagicSection {
+            priority: priority.parse().ok()?,
+            mime_type: mime_type.to_string(),
+            rules: vec![],
+        })
+    }
+
+    fn parse_rule(data: &mut &[u8]) -> Option<MagicRule> {
+        let indent = take_number(data).unwrap_or_default();
+
+        skip_byte(data, b'>')?;
+
+        let start_offset = take_number(data)?;
+
+        skip_byte(data, b'=')?;
+
+        let value_length = u16::from_be_bytes([*data.first()?, *data.get(1)?]) as usize;
+        *data = &data[2..];
+
+        let mut value = take_bytes(data, value_length)?;
+        let mut mask = None;
+        let mut word_size = 1;
+        let mut range_length = 1;
+
+        loop {
+            let (marker, rest) = data.split_first()?;
+            *data = rest;
+
+            match marker {
+                b'&' => mask = Some(take_bytes(data, value_length)?),
+                b'~' => word_size = take_number(data)?,
+                b'+' => range_length = take_number(data)?,
+                b'\n' => break,
+                // Unknown extensions are skipped, as required by the specification.
+                _ => {
+                    take_until(data, b'\n')?;
+                    break;
+                }
+            }
+        }
+
+        // The values of the multi-byte words are stored as big endian.
+        //
+        if word_size > 1 && cfg!(target_endian = "little") {
+            for bytes in mask.iter_mut().chain([&mut value]) {
+                bytes
+                    .chunks_exact_mut(word_size)
+                    .for_each(|word| word.reverse());
+            }
+        }
+
+        Some(MagicRule {
+            indent,
+            start_offset,
+            value,
+            mask,
+            range_length: range_length.max(1),
+        })
+    }
+
+    fn mime_type(&self, data: &[u8]) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|section| Self::match_rules(&section.rules, 0, data))
+            .map(|section| section.mime_type.as_str())
+    }
+
+    // The rules of a level are alternatives; a rule with children matches only if one of them matches
+    // as well.
+    //
+    fn match_rules(rules: &[MagicRule], indent: usize, data: &[u8]) -> bool {
+        let mut index = 0;
+
+        while index < rules.len() {
+            let children_end = rules[index + 1..]
+                .iter()
+                .position(|rule| rule.indent <= indent)
+                .map_or(rules.len(), |position| index + 1 + position);
+            let children = &rules[index + 1..children_end];
+
+            if rules[index].matches(data)
+                && (children.is_empty() || Self::match_rules(children, indent + 1, data))
+            {
+                return true;
+            }
+
+            index = children_end;
+        }
+
+        false
+    }
+}
+
+impl MagicRule {
+    fn matches(&self, data: &[u8]) -> bool {
+        (self.start_offset..self.start_offset + self.range_length).any(|offset| {
+            let Some(window) = data.get(offset..offset + self.value.len()) else {
+                return false;
+            };
+
+            match &self.mask {
+                Some(mask) => window
+                    .iter()
+                    .zip(mask)
+                    .zip(&self.value)
+                    .all(|((byte, mask), value)| byte & mask == value & mask),
+                None => window == self.value,
+            }
+        })
+    }
+}
+
+// Returns the bytes before the delimiter, and skips it.
+//
+fn take_until<'a>(data: &mut &'a [u8], delimiter: u8) -> Option<&'a [u8]> {
+    let position = data.iter().position(|byte| *byte == delimiter)?;
+    let taken = &data[..position];
+
+    *data = &data[position + 1..];
+
+    Some(taken)
+}
+
+fn skip_byte(data: &mut &[u8], byte: u8) -> Option<()> {
+    *data = data.strip_prefix(&[byte])?;
+
+    Some(())
+}
+
+fn take_bytes(data: &mut &[u8], length: usize) -> Option<Vec<u8>> {
+    let taken = data.get(..length)?.to_vec();
+
+    *data = &data[length..];
+
+    Some(taken)
+}
+
+// Decimal; None if there are no digits.
+//
+fn take_number(data: &mut &[u8]) -> Option<usize> {
+    let digits_count = data.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&data[..digits_count])
+        .ok()?
+        .parse()
+        .ok()?;
+
+    *data = &data[digits_count..];
+
+    Some(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mime_database(globs: &str) -> MimeDatabase {
+        let mut database = MimeDatabase::default();
+        database.add_globs(globs);
+        database
+    }
+
+    // `[<indent>]><offset>=<value>[+<range length>]`, without masks.
+    //
+    fn magic_rule(indent: usize, offset: usize, value: &[u8], range_length: usize) -> Vec<u8> {
+        let indent = if indent > 0 {
+            indent.to_string()
+        } else {
+            String::new()
+        };
+        let mut rule = format!("{indent}>{offset}=").into_bytes();
+
+        rule.extend((value.len() as u16).to_be_bytes());
+        rule.extend(value);
+
+        if range_length > 1 {
+            rule.extend(format!("+{range_length}").bytes());
+        }
+
+        rule.push(b'\n');
+        rule
+    }
+
+    #[test]
+    fn higher_weights_win_then_longer_patterns() {
+        let database = mime_database(
+            "50:application/gzip:*.gz\n\
+             50:application/x-compressed-tar:*.tar.gz\n\
+             80:text/x-makefile:makefile\n\
+             10:text/x-log:*file\n\
+             60:application/x-backup:*~\n\
+             40:text/plain:*.txt\n",
+        );
+
+        assert_eq!(
+            database.glob_mime_type("backup.tar.gz"),
+            Some("application/x-compressed-tar")
+        );
+        assert_eq!(
+            database.glob_mime_type("backup.gz"),
+            Some("application/gzip")
+        );
+        assert_eq!(database.glob_mime_type("Makefile"), Some("text/x-makefile"));
+        assert_eq!(
+            database.glob_mime_type("notes.txt~"),
+            Some("application/x-backup")
+        );
+        assert_eq!(database.glob_mime_type("notes.md"), None);
+    }
+
+    #[test]
+    fn case_sensitive_globs_match_only_the_exact_case() {
+        let database = mime_database(
+            "50:text/x-csrc:*.c\n\
+             50:text/x-c++src:*.C:cs\n\
+             50:text/x-readme:README:cs\n",
+        );
+
+        assert_eq!(database.glob_mime_type("main.C"), Some("text/x-c++src"));
+        assert_eq!(database.glob_mime_type("main.c"), Some("text/x-csrc"));
+        assert_eq!(database.glob_mime_type("README"), Some("text/x-readme"));
+        assert_eq!(database.glob_mime_type("readme"), None);
+    }
+
+    #[test]
+    fn only_a_leading_exclamation_mark_negates_a_class() {
+        let database = mime_database(
+            "50:text/x-negated:*.[!a-c]x\n\
+             50:text/x-listed:*.[a!^\\]y\n",
+        );
+
+        assert_eq!(database.glob_mime_type("file.dx"), Some("text/x-negated"));
+        assert_eq!(database.glob_mime_type("file.bx"), None);
+
+        for filename in ["file.ay", "file.!y", "file.^y", "file.\\y"] {
+            assert_eq!(database.glob_mime_type(filename), Some("text/x-listed"));
+        }
+
+        assert_eq!(database.glob_mime_type("file.by"), None);
+    }
+
+    #[test]
+    fn nested_magic_rules_require_a_matching_child() {
+        let mut magic = MAGIC_HEADER.to_vec();
+
+        magic.extend(b"[50:application/zip]\n");
+        magic.extend(magic_rule(0, 0, b"PK", 1));
+        magic.extend(b"[60:application/epub+zip]\n");
+        magic.extend(magic_rule(0, 0, b"PK", 1));
+        magic.extend(magic_rule(1, 30, b"mimetype", 1));
+        magic.extend(magic_rule(2, 38, b"application/epub", 4));
+
+        let database = MagicDatabase::new(MagicDatabase::parse(&magic));
+
+        let epub = b"PK\x03\x04--------------------------mimetype..application/epub+zip";
+        let zip_with_mimetype = b"PK\x03\x04--------------------------mimetype..text/plain";
+        let text = b"plain text";
+
+        assert_eq!(database.mime_type(epub), Some("application/epub+zip"));
+        assert_eq!(
+            database.mime_type(zip_with_mimetype),
+            Some("application/zip")
+        );
+        assert_eq!(database.mime_type(text), None);
+        assert_eq!(database.extent, 38 + 4 + "application/epub".len());
+    }
+
+    #[test]
+    fn higher_priority_magic_sections_are_checked_first() {
+        let mut magic = MAGIC_HEADER.to_vec();
+
+        magic.extend(b"[20:text/x-generic-script]\n");
+        magic.extend(magic_rule(0, 0, b"#!", 1));
+        magic.extend(b"[40:text/x-python]\n");
+        magic.extend(magic_rule(0, 0, b"#!/usr/bin/python", 1));
+
+        let database = MagicDatabase::new(MagicDatabase::parse(&magic));
+
+        assert_eq!(
+            database.mime_type(b"#!/usr/bin/python3\n"),
+            Some("text/x-python")
+        );
+        assert_eq!(
+            database.mime_type(b"#!/bin/sh\n"),
+            Some("text/x-generic-script")
+        );
+    }
+}
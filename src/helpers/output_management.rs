@@ -0,0 +1,189 @@
+// Output of the text produced by a search entry (e.g. an emoji), according to the output mode.
+//
+// Typing and pasting are performed via xdotool, so they're Linux/X11-only; on other platforms, the
+// text is copied to the clipboard.
+//
+// Since the keystrokes must be received by the window that was focused before pm-spotlight, they're
+// sent by a detached process, after a delay, so that the caller can exit in the meanwhile.
+
+use std::{error, fmt, io};
+
+use serde::Deserialize;
+
+use super::clipboard_management::{
+    copy_to_clipboard, ClipboardBackendKind, ClipboardError, ClipboardSelection,
+};
+#[cfg(target_os = "linux")]
+use super::{
+    clipboard_management::build_backend, executables::program_in_path,
+    process_management::spawn_detached,
+};
+
+#[cfg(target_os = "linux")]
+const XDOTOOL_PROGRAM: &str = "xdotool";
+
+// Seconds, in the format accepted by xdotool/sleep.
+//
+#[cfg(target_os = "linux")]
+const FOCUS_DELAY: &str = "0.3";
+
+/// How the text of an executed entry is output; typing and pasting are Linux/X11-only.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// Copy to the clipboard.
+    #[default]
+    Clipboard,
+    /// Type the text in the previously focused window.
+    Type,
+    /// Copy to the CLIPBOARD selection, and paste in the previously focused window (via Ctrl+V, so it
+    /// doesn't work in most terminals).
+    Paste,
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct OutputSettings {
+    pub mode: OutputMode,
+    // Clipboard mode only.
+    pub selection: ClipboardSelection,
+    // Paste mode only: restore the previous clipboard content after pasting.
+    pub restore_clipboard: bool,
+    // None: autodetect.
+    pub clipboard_backend: Option<ClipboardBackendKind>,
+}
+
+/// Error raised when outputting the text; it's meant to be displayed to the user.
+#[derive(Debug)]
+pub enum OutputError {
+    Clipboard(ClipboardError),
+    /// The program required by the output mode (e.g. `xdotool`) is not installed.
+    ProgramNotFound(&'static str),
+    Io(io::Error),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clipboard(error) => write!(f, "{error}"),
+            Self::ProgramNotFound(program) => write!(
+                f,
+                "Program `{program}` not found; install it, or configure another output mode"
+            ),
+            Self::Io(error) => write!(f, "Output error: {error}"),
+        }
+    }
+}
+
+impl error::Error for OutputError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Clipboard(error) => Some(error),
+            Self::ProgramNotFound(_) => None,
+            Self::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<ClipboardError> for OutputError {
+    fn from(error: ClipboardError) -> Self {
+        Self::Clipboard(error)
+    }
+}
+
+impl From<io::Error> for OutputError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+// `cursor_offset` is the number of chars, from the end of the text, to move the cursor back by, after
+// typing/pasting; it's ignored when copying to the clipboard.
+//
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+pub(crate) fn output_text(
+    text: String,
+    cursor_offset: usize,
+    settings: OutputSettings,
+) -> Result<(), OutputError> {
+    #[cfg(not(target_os = "linux"))]
+    {
+        copy_to_clipboard(text, settings.selection, None, settings.clipboard_backend)?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if !matches!(settings.mode, OutputMode::Clipboard) && !program_in_path(XDOTOOL_PROGRAM) {
+            return Err(OutputError::ProgramNotFound(XDOTOOL_PROGRAM));
+        }
+
+        match settings.mode {
+            OutputMode::Clipboard => {
+                copy_to_clipboard(text, settings.selection, None, settings.clipboard_backend)?
+            }
+            OutputMode::Type => {
+                let script = format!(
+                    r#"{XDOTOOL_PROGRAM} sleep {FOCUS_DELAY} type --clearmodifiers -- "$1"{}"#,
+                    move_cursor_command(cursor_offset)
+                );
+
+                spawn_detached_script(&script, &[text])?;
+            }
+            OutputMode::Paste => {
+                let backend = build_backend(settings.clipboard_backend);
+
+                // Restoring is best-effort; if the clipboard can't be read, or the backend can't be
+                // used by the detached process, the pasted text is left in the clipboard.
+                //
+                let restore = if settings.restore_clipboard {
+                    backend.shell_copy_command().zip(backend.read().ok())
+                } else {
+                    None
+                };
+
+                backend.copy(&text, ClipboardSelection::Clipboard, None)?;
+
+                let mut script = format!(
+                    "{XDOTOOL_PROGRAM} sleep {FOCUS_DELAY} key --clearmodifiers ctrl+v{}",
+                    move_cursor_command(cursor_offset)
+                );
+                let mut args = vec![];
+
+                // The target program may read the clipboard asynchronously, so wait before restoring.
+                //
+                if let Some((copy_command, previous_clipboard)) = restore {
+                    script.push_str(&format!(
+                        r#" && sleep {FOCUS_DELAY} && printf '%s' "$1" | {copy_command}"#
+                    ));
+                    args.push(previous_clipboard);
+                }
+
+                spawn_detached_script(&script, &args)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn move_cursor_command(cursor_offset: usize) -> String {
+    if cursor_offset > 0 {
+        format!(" && xdotool key --clearmodifiers --repeat {cursor_offset} Left")
+    } else {
+        String::new()
+    }
+}
+
+// The arguments are passed as positional parameters, so that they don't need to be escaped.
+//
+#[cfg(target_os = "linux")]
+fn spawn_detached_script(script: &str, args: &[String]) -> Result<(), OutputError> {
+    let script_args = ["-c", script, "sh"]
+        .into_iter()
+        .map(str::to_string)
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>();
+
+    spawn_detached("sh", &script_args)?;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::{
+    ffi::OsStr,
+    io,
+    os::unix::process::CommandExt,
+    process::{Command, Stdio},
+    thread,
+};
+
+// Starts a program that is intended to outlive pm-spotlight (e.g. the program opening a file).
+//
+// The program runs in a new session, so that it's not affected by the signals of the terminal
+// pm-spotlight may have been started from, and the standard streams are redirected to /dev/null.
+// Instead of double forking, the child is reaped by a thread, so that it doesn't linger as zombie while
+// pm-spotlight is resident; on exit, it's reparented to init.
+//
+// Only the errors in starting the program (e.g. not found) are returned; the exit status is not
+// checked, since programs may run for an indefinite amount of time.
+//
+pub fn spawn_detached(program: &str, args: &[impl AsRef<OsStr>]) -> io::Result<()> {
+    let mut command = Command::new(program);
+
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // SAFETY: setsid() is async-signal-safe, so it can be invoked between fork and exec.
+    //
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        });
+    }
+
+    let mut child = command.spawn()?;
+
+    thread::spawn(move || child.wait());
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+// Trash management, according to the freedesktop.org trash specification.
+//
+// Only the home trash (`$XDG_DATA_HOME/Trash`) is supported; files on other filesystems can't be moved
+// there (the specification requires a per-filesystem trash directory), so trashing them fails.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use super::{filenames::percent_encode_path, xdg_data_dirs::xdg_data_home};
+
+const TRASH_DIRNAME: &str = "Trash";
+const TRASHINFO_EXTENSION: &str = "trashinfo";
+// Local time, without time zone, as required by the specification.
+const DELETION_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Clone)]
+pub struct TrashedFile {
+    pub original_path: PathBuf,
+    // Location of the file in the trash.
+    pub trashed_path: PathBuf,
+    pub info_path: PathBuf,
+}
+
+// The path must be absolute.
+//
+pub fn trash_file(path: &Path) -> io::Result<TrashedFile> {
+    let trash_dir = trash_dir()?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+
+    fs::create_dir_all(&files_dir)?;
+    fs::create_dir_all(&info_dir)?;
+
+    let basename = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid path"))?
+        .to_string_lossy()
+        .to_string();
+
+    let (trashed_name, info_path) = reserve_trashed_name(&info_dir, &files_dir, &basename, path)?;
+    let trashed_path = files_dir.join(&trashed_name);
+
+    // The info file is removed on failure, since it would refer to a file that is not in the trash.
+    //
+    if let Err(error) = fs::rename(path, &trashed_path) {
+        let _ = fs::remove_file(&info_path);
+        return Err(error);
+    }
+
+    Ok(TrashedFile {
+        original_path: path.to_path_buf(),
+        trashed_path,
+        info_path,
+    })
+}
+
+// Moves the file back; fails if a file has been created at the original path in the meanwhile.
+//
+pub fn restore_file(trashed_file: &TrashedFile) -> io::Result<()> {
+    if path_taken(&trashed_file.original_path) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", trashed_file.original_path.display()),
+        ));
+    }
+
+    fs::rename(&trashed_file.trashed_path, &trashed_file.original_path)?;
+    fs::remove_file(&trashed_file.info_path)
+}
+
+fn trash_dir() -> io::Result<PathBuf> {
+    let data_home = xdg_data_home()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Home directory not found"))?;
+
+    Ok(data_home.join(TRASH_DIRNAME))
+}
+
+// Names are reserved by atomically creating the info file, so that concurrent trashing (e.g. by other
+// programs) can't pick the same name. On collision, a counter is added before the extension
+// (`report.2.pdf`).
+//
+// Returns the name, and the info file path.
+//
+fn reserve_trashed_name(
+    info_dir: &Path,
+    files_dir: &Path,
+    basename: &str,
+    original_path: &Path,
+) -> io::Result<(String, PathBuf)> {
+    let (stem, extension) = match basename.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (basename, None),
+    };
+
+    let info_content = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode_path(original_path),
+        Local::now().format(DELETION_DATE_FORMAT)
+    );
+
+    for counter in 1.. {
+        let name = match (counter, extension) {
+            (1, _) => basename.to_string(),
+            (_, Some(extension)) => format!("{stem}.{counter}.{extension}"),
+            (_, None) => format!("{stem}.{counter}"),
+        };
+
+        // Files may be in the trash without info file (e.g. if the info file has been deleted).
+        //
+        if path_taken(&files_dir.join(&name)) {
+            continue;
+        }
+
+        let info_path = info_dir.join(format!("{name}.{TRASHINFO_EXTENSION}"));
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(mut info_file) => {
+                if let Err(error) = info_file.write_all(info_content.as_bytes()) {
+                    let _ = fs::remove_file(&info_path);
+                    return Err(error);
+                }
+
+                return Ok((name, info_path));
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+
+    unreachable!()
+}
+
+// Differently from `Path::exists()`, symlinks are not followed, so that dangling ones count as well;
+// a rename would overwrite them.
+//
+fn path_taken(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, os::unix::fs::symlink, process};
+
+    use super::*;
+
+    #[test]
+    fn dangling_symlinks_in_the_trash_are_not_overwritten() {
+        let trash_dir = env::temp_dir().join(format!("pm-spotlight-test-{}-trash", process::id()));
+        let (files_dir, info_dir) = (trash_dir.join("files"), trash_dir.join("info"));
+
+        fs::create_dir_all(&files_dir).unwrap();
+        fs::create_dir_all(&info_dir).unwrap();
+        symlink("/nonexistent", files_dir.join("report.pdf")).unwrap();
+
+        let (name, info_path) = reserve_trashed_name(
+            &info_dir,
+            &files_dir,
+            "report.pdf",
+            Path::new("/home/user/report.pdf"),
+        )
+        .unwrap();
+
+        assert_eq!(name, "report.2.pdf");
+        assert_eq!(info_path, info_dir.join("report.2.pdf.trashinfo"));
+
+        let info_content = fs::read_to_string(&info_path).unwrap();
+
+        assert!(info_content.starts_with("[Trash Info]\nPath=/home/user/report.pdf\n"));
+
+        fs::remove_dir_all(trash_dir).unwrap();
+    }
+}
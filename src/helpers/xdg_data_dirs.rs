@@ -0,0 +1,37 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+// Relative to the home directory.
+const DEFAULT_XDG_DATA_HOME: &str = ".local/share";
+const DEFAULT_XDG_DATA_DIRS: &str = "/usr/local/share:/usr/share";
+
+// `$XDG_DATA_HOME` (default: `$HOME/.local/share`); relative values are invalid, so they're ignored.
+// None if the home directory is not found.
+//
+pub fn xdg_data_home() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home_dir| home_dir.join(DEFAULT_XDG_DATA_HOME)))
+}
+
+// Ordered from the highest to the lowest precedence: `$XDG_DATA_HOME`, then `$XDG_DATA_DIRS`.
+//
+pub fn xdg_data_dirs() -> Vec<PathBuf> {
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|data_dirs| !data_dirs.is_empty())
+        .unwrap_or_else(|| DEFAULT_XDG_DATA_DIRS.to_string());
+
+    xdg_data_home()
+        .into_iter()
+        .chain(
+            data_dirs
+                .split(':')
+                .filter(|dir| Path::new(dir).is_absolute())
+                .map(PathBuf::from),
+        )
+        .collect()
+}
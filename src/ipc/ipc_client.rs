@@ -0,0 +1,30 @@
+// Client side of the daemon mode, used by the `toggle`/`show`/`hide` commands.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+};
+
+use super::ipc_protocol::{socket_path, IpcCommand, REPLY_ERROR_PREFIX};
+
+pub fn send_command(command: IpcCommand) -> Result<(), String> {
+    let socket_path = socket_path()?;
+
+    let mut stream = UnixStream::connect(&socket_path).map_err(|error| match error.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => {
+            "The daemon is not running; start it with `pm-spotlight daemon`".to_string()
+        }
+        _ => format!("Couldn't connect to {}: {error}", socket_path.display()),
+    })?;
+
+    let mut reply = String::new();
+
+    writeln!(stream, "{}", command.to_line())
+        .and_then(|_| BufReader::new(&stream).read_line(&mut reply))
+        .map_err(|error| format!("IPC error: {error}"))?;
+
+    match reply.trim_end().strip_prefix(REPLY_ERROR_PREFIX) {
+        Some(error) => Err(error.to_string()),
+        None => Ok(()),
+    }
+}
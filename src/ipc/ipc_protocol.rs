@@ -0,0 +1,137 @@
+// Protocol between the daemon and the client commands (`toggle`, `show`, `hide`).
+//
+// Each connection carries a single command line, which the daemon answers with a single reply line:
+// `ok`, or `error: <message>`.
+
+use std::{
+    env,
+    fs::{self, DirBuilder},
+    io,
+    os::unix::fs::{DirBuilderExt, MetadataExt},
+    path::{Path, PathBuf},
+};
+
+const SOCKET_BASENAME: &str = "pm-spotlight.sock";
+const FALLBACK_SOCKET_DIRNAME_PREFIX: &str = "pm-spotlight-";
+const PRIVATE_DIR_MODE: u32 = 0o700;
+
+pub const REPLY_OK: &str = "ok";
+pub const REPLY_ERROR_PREFIX: &str = "error: ";
+
+pub enum IpcCommand {
+    // The query, if present, replaces the input text.
+    Show(Option<String>),
+    Hide,
+    Toggle,
+}
+
+impl IpcCommand {
+    // The query follows the command name, separated by a space, so that it can contain spaces.
+    // Newlines are replaced, since they terminate the command (and the input is single-line anyway).
+    //
+    pub fn to_line(&self) -> String {
+        match self {
+            Self::Show(Some(query)) => format!("show {}", query.replace('\n', " ")),
+            Self::Show(None) => "show".to_string(),
+            Self::Hide => "hide".to_string(),
+            Self::Toggle => "toggle".to_string(),
+        }
+    }
+
+    pub fn parse_line(line: &str) -> Result<Self, String> {
+        match line.split_once(' ') {
+            Some(("show", query)) => Ok(Self::Show(Some(query.to_string()))),
+            None if line == "show" => Ok(Self::Show(None)),
+            None if line == "hide" => Ok(Self::Hide),
+            None if line == "toggle" => Ok(Self::Toggle),
+            _ => Err(format!("Unknown command: `{line}`")),
+        }
+    }
+}
+
+// The socket is created in a directory private to the user, so that other users can't connect to it,
+// or impersonate the daemon: the runtime dir, or, if not set, a per-user directory in the (shared)
+// temporary dir. Since another user may have created the latter first, the ownership and permissions
+// of the directory are verified.
+//
+pub fn socket_path() -> Result<PathBuf, String> {
+    let uid = unsafe { libc::getuid() };
+
+    let socket_dir = match env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from) {
+        Some(runtime_dir) if runtime_dir.is_absolute() => runtime_dir,
+        _ => {
+            let socket_dir = env::temp_dir().join(format!("{FALLBACK_SOCKET_DIRNAME_PREFIX}{uid}"));
+
+            match DirBuilder::new().mode(PRIVATE_DIR_MODE).create(&socket_dir) {
+                Ok(()) => {}
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(error) => {
+                    return Err(format!("Couldn't create {}: {error}", socket_dir.display()))
+                }
+            }
+
+            socket_dir
+        }
+    };
+
+    verify_private_dir(&socket_dir, uid)?;
+
+    Ok(socket_dir.join(SOCKET_BASENAME))
+}
+
+// Symlinks are not followed, since they could point to a directory of another user.
+//
+fn verify_private_dir(dir: &Path, uid: u32) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(dir)
+        .map_err(|error| format!("Couldn't access {}: {error}", dir.display()))?;
+
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(format!(
+            "{} is not a directory private to the current user",
+            dir.display()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::fs::PermissionsExt, process};
+
+    use super::*;
+
+    #[test]
+    fn only_private_dirs_of_the_user_are_accepted() {
+        let uid = unsafe { libc::getuid() };
+        let dir = env::temp_dir().join(format!("pm-spotlight-test-{}-ipc", process::id()));
+
+        DirBuilder::new()
+            .mode(PRIVATE_DIR_MODE)
+            .create(&dir)
+            .unwrap();
+
+        assert!(verify_private_dir(&dir, uid).is_ok());
+        assert!(verify_private_dir(&dir, uid + 1).is_err());
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(verify_private_dir(&dir, uid).is_err());
+
+        fs::remove_dir(dir).unwrap();
+    }
+
+    #[test]
+    fn commands_round_trip() {
+        for command in [
+            IpcCommand::Show(Some("a b".to_string())),
+            IpcCommand::Show(None),
+            IpcCommand::Hide,
+            IpcCommand::Toggle,
+        ] {
+            let line = command.to_line();
+
+            assert_eq!(IpcCommand::parse_line(&line).unwrap().to_line(), line);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// Server side of the daemon mode; it receives the commands, and forwards them to the app.
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    thread,
+    time::Duration,
+};
+
+use super::ipc_protocol::{socket_path, IpcCommand, REPLY_ERROR_PREFIX, REPLY_OK};
+
+// Clients are served one at a time, so one that doesn't send its command (or doesn't read the reply)
+// must not block the others; the commands are short, so they're sent immediately.
+//
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub struct IpcServer {
+    listener: UnixListener,
+}
+
+impl IpcServer {
+    // Fails if another daemon is running. A socket file left by a terminated daemon is replaced.
+    //
+    // The socket permissions are not restricted, since its directory is private (see `socket_path()`).
+    //
+    pub fn bind() -> Result<Self, String> {
+        let socket_path = socket_path()?;
+
+        if socket_path.exists() {
+            if UnixStream::connect(&socket_path).is_ok() {
+                return Err(format!(
+                    "A daemon is already running (socket: {})",
+                    socket_path.display()
+                ));
+            }
+
+            fs::remove_file(&socket_path)
+                .map_err(|error| format!("Couldn't remove {}: {error}", socket_path.display()))?;
+        }
+
+        let listener = UnixListener::bind(&socket_path)
+            .map_err(|error| format!("Couldn't bind {}: {error}", socket_path.display()))?;
+
+        Ok(Self { listener })
+    }
+
+    // Serves in a separate thread; `on_command` is invoked from that thread.
+    //
+    pub fn serve(self, on_command: impl Fn(IpcCommand) + Send + 'static) {
+        thread::spawn(move || {
+            for stream in self.listener.incoming() {
+                // Errors concern a single client, so they don't stop the server.
+                //
+                if let Err(error) =
+                    stream.and_then(|stream| Self::serve_client(stream, &on_command))
+                {
+                    eprintln!("IPC error: {error}");
+                }
+            }
+        });
+    }
+
+    fn serve_client(mut stream: UnixStream, on_command: &impl Fn(IpcCommand)) -> io::Result<()> {
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+        let mut line = String::new();
+
+        // Connections without data are liveness probes (see `bind()`).
+        //
+        if BufReader::new(&stream).read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        let reply = match IpcCommand::parse_line(line.trim_end_matches('\n')) {
+            Ok(command) => {
+                on_command(command);
+                REPLY_OK.to_string()
+            }
+            Err(error) => format!("{REPLY_ERROR_PREFIX}{error}"),
+        };
+
+        writeln!(stream, "{reply}")
+    }
+}
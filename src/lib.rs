@@ -0,0 +1,78 @@
+//! Search engine of Poor Man's Spotlight, usable independently of the bundled frontends.
+//!
+//! The [`SearchManager`](search::search_manager::SearchManager) dispatches each pattern to the
+//! [`Searcher`](search::searcher::Searcher) that handles it (by prefix), and the results are delivered,
+//! possibly from a separate thread, to a [`ResultSink`](search::result_sink::ResultSink) implemented
+//! by the caller:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use pm_spotlight::{
+//!     config::config_manager::ConfigManager,
+//!     search::{
+//!         result_sink::ResultSink, search_manager::SearchManager,
+//!         search_result_entry::SearchResultEntry,
+//!     },
+//! };
+//!
+//! struct PrintSink;
+//!
+//! impl ResultSink for PrintSink {
+//!     fn update_list(&self, entries: Vec<SearchResultEntry>) {
+//!         for entry in entries {
+//!             println!("{}", entry.label);
+//!         }
+//!     }
+//!
+//!     fn execute_entry(&self, _alternate: bool) {}
+//! }
+//!
+//! let config = ConfigManager::new(None).load_configuration().unwrap();
+//! let mut search_manager = SearchManager::new(config, None);
+//!
+//! search_manager.search(":smile".to_string(), Arc::new(PrintSink));
+//! ```
+
+pub mod config {
+    pub mod config_manager;
+    #[cfg(target_os = "linux")]
+    pub mod config_watcher;
+}
+
+// Only the modules whose types are part of the API (e.g. via the configuration), or that are used by the
+// bundled frontends, are public.
+//
+pub mod helpers {
+    pub mod clipboard_management;
+    #[cfg(target_os = "linux")]
+    pub(crate) mod executables;
+    pub mod file_preview;
+    pub(crate) mod filenames;
+    #[cfg(target_os = "linux")]
+    pub(crate) mod icon_theme;
+    #[cfg(target_os = "linux")]
+    pub(crate) mod mime_types;
+    pub mod output_management;
+    #[cfg(target_os = "linux")]
+    pub(crate) mod process_management;
+    #[cfg(target_os = "linux")]
+    pub(crate) mod trash;
+    #[cfg(target_os = "linux")]
+    pub(crate) mod xdg_data_dirs;
+}
+
+pub mod search {
+    pub mod action;
+    pub mod emoji_searcher;
+    pub mod execute_error;
+    #[cfg(target_os = "linux")]
+    pub mod file_searcher;
+    pub mod result_sink;
+    pub mod search_manager;
+    pub mod search_result_entry;
+    pub mod searcher;
+    pub mod searcher_settings;
+    pub mod snippet_searcher;
+    pub mod stdin_searcher;
+}
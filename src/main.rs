@@ -1,34 +1,189 @@
 #![cfg_attr(target_os = "windows", windows_subsystem = "windows")]
 
-mod gui {
+mod events {
     pub mod message_event;
-    pub mod pm_spotlight_app;
+    pub mod message_sender;
 }
 
-mod search {
-    pub mod emoji_searcher;
-    #[cfg(target_os = "linux")]
-    pub mod file_searcher;
-    pub mod search_manager;
-    pub mod search_result_entry;
-    pub mod searcher;
+mod gui {
+    pub mod pm_spotlight_app;
+    pub mod preview_pane;
+    pub mod result_browser;
 }
 
-mod helpers {
-    pub mod clipboard_management;
-    pub mod filenames;
+mod tui {
+    pub mod pm_spotlight_tui;
 }
 
-mod config {
-    pub mod config_manager;
+#[cfg(target_os = "linux")]
+mod ipc {
+    pub mod ipc_client;
+    pub mod ipc_protocol;
+    pub mod ipc_server;
 }
 
-use config::config_manager::ConfigManager;
+mod cli;
+mod frontend;
+
+use std::{io, process};
+
+use clap::Parser;
+
+use pm_spotlight::{
+    config::config_manager::{Config, ConfigManager},
+    search::search_manager::SearchManager,
+};
+
+use cli::{Cli, Command, ConfigAction};
+use frontend::{Frontend, CANCEL_EXIT_STATUS};
 use gui::pm_spotlight_app::PMSpotlightApp;
-use search::search_manager::SearchManager;
+#[cfg(target_os = "linux")]
+use ipc::{ipc_client::send_command, ipc_protocol::IpcCommand, ipc_server::IpcServer};
+use tui::pm_spotlight_tui::PMSpotlightTui;
 
 fn main() {
-    let config = ConfigManager::load_configuration();
-    let search_manager = SearchManager::new(config);
-    PMSpotlightApp::build(search_manager).run();
+    let cli = Cli::parse();
+    let config_manager = ConfigManager::new(cli.config.clone());
+
+    match cli.command {
+        Some(Command::Config {
+            action: ConfigAction::Check,
+        }) => check_configuration(&config_manager),
+        #[cfg(target_os = "linux")]
+        Some(Command::Daemon) => run_daemon(config_manager),
+        #[cfg(target_os = "linux")]
+        Some(Command::Toggle) => send_daemon_command(IpcCommand::Toggle),
+        #[cfg(target_os = "linux")]
+        Some(Command::Show { query }) => send_daemon_command(IpcCommand::Show(query)),
+        #[cfg(target_os = "linux")]
+        Some(Command::Hide) => send_daemon_command(IpcCommand::Hide),
+        None => run_frontend(cli, config_manager),
+    }
+}
+
+fn run_frontend(cli: Cli, config_manager: ConfigManager) {
+    let (config, config_messages) = load_configuration(&config_manager);
+
+    let mut search_manager = SearchManager::new(config, cli.searcher);
+
+    // In dmenu mode, the items are always listed, since the configuration is mostly irrelevant; the
+    // messages are printed before the frontend starts, since the TUI takes over the terminal.
+    //
+    if cli.dmenu {
+        search_manager.set_stdin_items(read_stdin_items());
+
+        for message in &config_messages {
+            eprintln!("{message}");
+        }
+    }
+
+    let mut frontend: Box<dyn Frontend> = if cli.tui {
+        Box::new(PMSpotlightTui::build(
+            search_manager,
+            config_manager,
+            cli.print,
+        ))
+    } else {
+        Box::new(PMSpotlightApp::build(
+            search_manager,
+            config_manager,
+            cli.print,
+            false,
+        ))
+    };
+
+    if let Some(query) = &cli.query {
+        frontend.set_query(query);
+    }
+
+    // Configuration messages take precedence over the query results, since a search would clear them;
+    // in this case, the query is searched on the next input change.
+    //
+    if cli.dmenu {
+        frontend.start_search();
+    } else if !config_messages.is_empty() {
+        frontend.display_errors(config_messages);
+    } else if cli.query.is_some() {
+        frontend.start_search();
+    }
+
+    frontend.run();
+
+    // The frontends exit on successful execution, so returning means that the window was closed
+    // without selecting.
+    //
+    if cli.dmenu || cli.print {
+        process::exit(CANCEL_EXIT_STATUS);
+    }
+}
+
+// The window is hidden until the first `show`/`toggle` command; the configuration messages are displayed
+// then.
+//
+#[cfg(target_os = "linux")]
+fn run_daemon(config_manager: ConfigManager) {
+    let ipc_server = IpcServer::bind().unwrap_or_else(|error| {
+        eprintln!("{error}");
+        process::exit(1);
+    });
+
+    let (config, config_messages) = load_configuration(&config_manager);
+    let search_manager = SearchManager::new(config, None);
+
+    let mut app = PMSpotlightApp::build(search_manager, config_manager, false, true);
+
+    app.serve_commands(ipc_server);
+
+    if !config_messages.is_empty() {
+        app.display_errors(config_messages);
+    }
+
+    app.run();
+}
+
+#[cfg(target_os = "linux")]
+fn send_daemon_command(command: IpcCommand) {
+    if let Err(error) = send_command(command) {
+        eprintln!("{error}");
+        process::exit(1);
+    }
+}
+
+// On error, start anyway, so that the error can be displayed in the frontend.
+//
+fn load_configuration(config_manager: &ConfigManager) -> (Config, Vec<String>) {
+    match config_manager.load_configuration() {
+        Ok(config) => {
+            let warnings = config.warnings.clone();
+            (config, warnings)
+        }
+        Err(error) => (Config::default(), vec![error.to_string()]),
+    }
+}
+
+// Empty lines are skipped.
+//
+fn read_stdin_items() -> Vec<String> {
+    io::stdin()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// Exits with status 1 on error; warnings don't affect the status.
+//
+fn check_configuration(config_manager: &ConfigManager) {
+    match config_manager.load_configuration() {
+        Ok(config) => {
+            for warning in &config.warnings {
+                eprintln!("{warning}");
+            }
+            println!("Configuration OK");
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,55 @@
+/// Id of the action opening the folder containing the entry; frontends can bind it to a key.
+pub const OPEN_FOLDER_ACTION_ID: &str = "open-folder";
+
+/// Secondary operation offered for an entry, in addition to the (alternate) execution; actions are
+/// listed via [`Searcher::actions`](super::searcher::Searcher::actions), and performed via
+/// [`Searcher::execute_action`](super::searcher::Searcher::execute_action).
+#[derive(Clone)]
+pub struct Action {
+    /// Identifies the action within the searcher.
+    pub id: &'static str,
+    pub label: String,
+    /// If set, the frontend asks for a text, which is passed on execution.
+    pub input: Option<ActionInput>,
+    /// If set, the frontend asks for confirmation (with this message) before executing.
+    pub confirmation: Option<String>,
+    /// If true, the frontend stays open, and repeats the search, after executing (e.g. when the entry
+    /// has been renamed); otherwise, it behaves like after an execution.
+    pub refresh: bool,
+}
+
+#[derive(Clone)]
+pub struct ActionInput {
+    pub prompt: String,
+    pub default_value: String,
+}
+
+impl Action {
+    pub fn new(id: &'static str, label: &str) -> Self {
+        Self {
+            id,
+            label: label.to_string(),
+            input: None,
+            confirmation: None,
+            refresh: false,
+        }
+    }
+
+    pub fn with_input(mut self, prompt: &str, default_value: &str) -> Self {
+        self.input = Some(ActionInput {
+            prompt: prompt.to_string(),
+            default_value: default_value.to_string(),
+        });
+        self
+    }
+
+    pub fn with_confirmation(mut self, message: String) -> Self {
+        self.confirmation = Some(message);
+        self
+    }
+
+    pub fn with_refresh(mut self) -> Self {
+        self.refresh = true;
+        self
+    }
+}
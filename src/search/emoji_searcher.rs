@@ -1,14 +1,19 @@
-use fltk::{
-    app::Sender,
-    image::{PngImage, SharedImage},
-};
 use phf::phf_map;
-use std::process;
+use std::sync::Arc;
 
-use super::{search_result_entry::SearchResultEntry, searcher::Searcher};
+use super::{
+    execute_error::ExecuteError,
+    result_sink::ResultSink,
+    search_result_entry::{EntryIcon, SearchResultEntry},
+    searcher::Searcher,
+    searcher_settings::searcher_settings,
+};
 use crate::{
-    gui::message_event::MessageEvent::{self, UpdateList},
-    helpers::clipboard_management::copy_to_clipboard,
+    config::config_manager::Config,
+    helpers::{
+        clipboard_management::copy_to_clipboard,
+        output_management::{output_text, OutputSettings},
+    },
 };
 
 // The reference for the Emoji is Emojipedia.
@@ -161,49 +166,377 @@ const EMOJI_ICON_PATTERNS: phf::Map<&str, (&str, &[u8])> = phf_map! {
     "↵" => ("enter",                                                   include_bytes!("../../resources/emoji_icons/enter.png")),
 };
 
-pub struct EmojiSearcher {}
+// Shortcode aliases, as typed in GitHub (gemoji) and Slack, e.g. `:tada:`. They're matched in addition to
+// the labels above; an alias followed by the closing colon selects the emoji immediately.
+//
+const EMOJI_SHORTCODES: phf::Map<&str, &str> = phf_map! {
+    "+1"                             => "👍",
+    "-1"                             => "👎",
+    "angry"                          => "😠",
+    "anguished"                      => "😧",
+    "astonished"                     => "😲",
+    "bald"                           => "🦲",
+    "birthday"                       => "🎂",
+    "blush"                          => "😊",
+    "bouquet"                        => "💐",
+    "bow"                            => "🙇",
+    "boy"                            => "👦",
+    "bulb"                           => "💡",
+    "call_me_hand"                   => "🤙",
+    "child"                          => "🧒",
+    "clinking_glasses"               => "🥂",
+    "clown_face"                     => "🤡",
+    "cocktail"                       => "🍸",
+    "cold_face"                      => "🥶",
+    "cold_sweat"                     => "😰",
+    "confetti_ball"                  => "🎊",
+    "confused"                       => "😕",
+    "couple_with_heart_woman_man"    => "👩‍❤️‍👨",
+    "couplekiss_man_woman"           => "👩‍❤️‍💋‍👨",
+    "crossed_fingers"                => "🤞",
+    "cry"                            => "😢",
+    "cursing_face"                   => "🤬",
+    "dancer"                         => "💃",
+    "dash"                           => "💨",
+    "disappointed"                   => "😞",
+    "disguised_face"                 => "🥸",
+    "dizzy_face"                     => "😵",
+    "drooling_face"                  => "🤤",
+    "duck"                           => "🦆",
+    "exploding_head"                 => "🤯",
+    "face_holding_back_tears"        => "🥹",
+    "face_with_head_bandage"         => "🤕",
+    "face_with_peeking_eye"          => "🫣",
+    "face_with_rolling_eyes"         => "🙄",
+    "face_with_spiral_eyes"          => "😵‍💫",
+    "face_with_thermometer"          => "🤒",
+    "facepalm"                       => "🤦",
+    "fearful"                        => "😨",
+    "fireworks"                      => "🎆",
+    "flushed"                        => "😳",
+    "fu"                             => "🖕",
+    "girl"                           => "👧",
+    "grimacing"                      => "😬",
+    "grin"                           => "😁",
+    "guitar"                         => "🎸",
+    "gun"                            => "🔫",
+    "hamburger"                      => "🍔",
+    "hand_over_mouth"                => "🤭",
+    "hankey"                         => "💩",
+    "heart"                          => "❤️",
+    "heart_eyes"                     => "😍",
+    "hot_face"                       => "🥵",
+    "hugging_face"                   => "🤗",
+    "hugs"                           => "🤗",
+    "hushed"                         => "😯",
+    "ice_skate"                      => "⛸️",
+    "innocent"                       => "😇",
+    "joy"                            => "😂",
+    "kissing_heart"                  => "😘",
+    "laughing"                       => "😆",
+    "man"                            => "👨",
+    "man_dancing"                    => "🕺",
+    "melting_face"                   => "🫠",
+    "metal"                          => "🤘",
+    "middle_finger"                  => "🖕",
+    "money_mouth_face"               => "🤑",
+    "monocle_face"                   => "🧐",
+    "muscle"                         => "💪",
+    "nauseated_face"                 => "🤢",
+    "nerd_face"                      => "🤓",
+    "neutral_face"                   => "😐",
+    "no_good"                        => "🙅",
+    "no_mouth"                       => "😶",
+    "ok_woman"                       => "🙆",
+    "open_mouth"                     => "😮",
+    "partying_face"                  => "🥳",
+    "peach"                          => "🍑",
+    "pensive"                        => "😔",
+    "pinched_fingers"                => "🤌",
+    "pizza"                          => "🍕",
+    "pleading_face"                  => "🥺",
+    "poop"                           => "💩",
+    "pray"                           => "🙏",
+    "princess"                       => "👸",
+    "raised_eyebrow"                 => "🤨",
+    "raising_hand"                   => "🙋",
+    "relaxed"                        => "☺",
+    "relieved"                       => "😌",
+    "robot"                          => "🤖",
+    "rofl"                           => "🤣",
+    "roll_eyes"                      => "🙄",
+    "saluting_face"                  => "🫡",
+    "satisfied"                      => "😆",
+    "scream"                         => "😱",
+    "see_no_evil"                    => "🙈",
+    "shit"                           => "💩",
+    "shrug"                          => "🤷",
+    "skier"                          => "⛷️",
+    "skull"                          => "💀",
+    "sleeping"                       => "😴",
+    "slightly_frowning_face"         => "🙁",
+    "slightly_smiling_face"          => "🙂",
+    "smile"                          => "😄",
+    "smiley"                         => "😃",
+    "smiling_face_with_tear"         => "🥲",
+    "smiling_imp"                    => "😈",
+    "smirk"                          => "😏",
+    "sneezing_face"                  => "🤧",
+    "sob"                            => "😭",
+    "star_struck"                    => "🤩",
+    "stuck_out_tongue_winking_eye"   => "😜",
+    "sunglasses"                     => "😎",
+    "superhero_man"                  => "🦸‍♂️",
+    "sweat_smile"                    => "😅",
+    "tada"                           => "🎉",
+    "thermometer"                    => "🌡️",
+    "thinking"                       => "🤔",
+    "thinking_face"                  => "🤔",
+    "thumbsdown"                     => "👎",
+    "thumbsup"                       => "👍",
+    "triumph"                        => "😤",
+    "tropical_drink"                 => "🍹",
+    "unamused"                       => "😒",
+    "upside_down_face"               => "🙃",
+    "vomiting_face"                  => "🤮",
+    "wave"                           => "👋",
+    "wink"                           => "😉",
+    "woman"                          => "👩",
+    "woozy_face"                     => "🥴",
+    "yawning_face"                   => "🥱",
+    "yum"                            => "😋",
+    "zany_face"                      => "🤪",
+    "zipper_mouth_face"              => "🤐",
+    "zombie"                         => "🧟",
+};
+
+searcher_settings! {
+    pub struct EmojiSearcherSettings {
+        default_prefix: ":",
+    }
+}
+
+// Rank of a match; lower values are displayed first.
+//
+const RANK_EXACT_ALIAS: u8 = 0;
+const RANK_SUBSTRING: u8 = 1;
+
+pub struct EmojiSearcher {
+    settings: EmojiSearcherSettings,
+    output_settings: OutputSettings,
+}
 
 impl EmojiSearcher {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(config: &Config) -> Self {
+        let settings = config.searchers.emoji.clone();
+        let output_settings = settings.output_settings(config);
+
+        Self {
+            settings,
+            output_settings,
+        }
+    }
+
+    fn aliases(emoji: &str) -> impl Iterator<Item = &'static str> + '_ {
+        EMOJI_SHORTCODES
+            .entries()
+            .filter(move |(_, alias_emoji)| **alias_emoji == emoji)
+            .map(|(alias, _)| *alias)
+    }
+
+    fn match_rank(emoji: &str, patterns: &str, pattern: &str) -> Option<u8> {
+        if Self::aliases(emoji).any(|alias| alias == pattern) {
+            Some(RANK_EXACT_ALIAS)
+        } else if patterns.contains(pattern)
+            || Self::aliases(emoji).any(|alias| alias.contains(pattern))
+        {
+            Some(RANK_SUBSTRING)
+        } else {
+            None
+        }
+    }
+
+    fn build_entry(
+        emoji: &str,
+        patterns: &str,
+        image_bytes: &'static [u8],
+        search_id: u32,
+    ) -> SearchResultEntry {
+        SearchResultEntry::new(
+            Some(EntryIcon::Png(image_bytes)),
+            patterns.to_string(),
+            Some(emoji.to_string()),
+            search_id,
+            true,
+        )
+        .with_detail(Self::build_detail(emoji))
+    }
+
+    // Code points and shortcodes, e.g. `U+1F44D  :+1: :thumbsup:`.
+    //
+    fn build_detail(emoji: &str) -> String {
+        let code_points = emoji
+            .chars()
+            .map(|char| format!("U+{:04X}", char as u32))
+            .collect::<Vec<_>>();
+
+        let mut shortcodes = Self::aliases(emoji)
+            .map(|alias| format!(":{alias}:"))
+            .collect::<Vec<_>>();
+
+        // The map is unordered.
+        //
+        shortcodes.sort();
+
+        format!("{}  {}", code_points.join(" "), shortcodes.join(" "))
+            .trim_end()
+            .to_string()
     }
 }
 
 impl Searcher for EmojiSearcher {
     fn handles(&self, pattern: &str) -> bool {
-        pattern.starts_with(':')
+        self.settings.strip_prefix(pattern).is_some()
     }
 
-    fn search(&mut self, pattern: String, sender: Sender<MessageEvent>, search_id: u32) {
-        let pattern = pattern.chars().skip(1).collect::<String>();
+    fn search(&mut self, pattern: String, result_sink: Arc<dyn ResultSink>, search_id: u32) {
+        let pattern = self.settings.strip_prefix(&pattern).unwrap_or(&pattern);
+
+        // A closing colon (`:tada:`) requests an exact shortcode match; if there is one, it's selected
+        // straight away.
+        //
+        if let Some(shortcode) = pattern.strip_suffix(':') {
+            if let Some(emoji) = EMOJI_SHORTCODES.get(shortcode) {
+                let (patterns, image_bytes) = EMOJI_ICON_PATTERNS.get(emoji).unwrap();
+                let entry = Self::build_entry(emoji, patterns, image_bytes, search_id);
+
+                result_sink.update_list(vec![entry]);
+                result_sink.execute_entry(false);
+                return;
+            }
+        }
 
         if !pattern.is_empty() {
-            let search_result = EMOJI_ICON_PATTERNS
+            let mut ranked_matches = EMOJI_ICON_PATTERNS
                 .into_iter()
                 .filter_map(|(emoji, (patterns, image_bytes))| {
-                    if patterns.contains(&pattern) {
-                        let shared_image =
-                            SharedImage::from_image(PngImage::from_data(image_bytes).unwrap());
-
-                        Some(SearchResultEntry::new(
-                            shared_image.ok(),
-                            patterns.to_string(),
-                            Some(emoji.to_string()),
-                            search_id,
-                            true,
-                        ))
-                    } else {
-                        None
-                    }
+                    Self::match_rank(emoji, patterns, pattern)
+                        .map(|rank| (rank, *emoji, *patterns, *image_bytes))
+                })
+                .collect::<Vec<_>>();
+
+            // The map is unordered, so sort by label as well, in order to have a stable result.
+            //
+            ranked_matches.sort_by_key(|(rank, _, patterns, _)| (*rank, *patterns));
+
+            // Matches on the aliases are not displayed, since the aliases are not part of the label.
+            //
+            let search_result = ranked_matches
+                .into_iter()
+                .map(|(_, emoji, patterns, image_bytes)| {
+                    let match_ranges = patterns
+                        .match_indices(pattern)
+                        .map(|(start, found)| start..start + found.len())
+                        .collect();
+
+                    Self::build_entry(emoji, patterns, image_bytes, search_id)
+                        .with_match_ranges(match_ranges)
                 })
                 .collect();
 
-            sender.send(UpdateList(search_result));
+            result_sink.update_list(self.settings.limit_results(search_result));
         }
     }
 
-    fn execute(&self, emoji: String) {
-        copy_to_clipboard(emoji);
-        process::exit(0);
+    fn execute(&self, emoji: String) -> Result<(), ExecuteError> {
+        output_text(emoji, 0, self.output_settings)?;
+
+        Ok(())
+    }
+
+    // Multiple emojis are always copied, one per line, regardless of the output mode.
+    //
+    fn execute_batch(&self, emojis: Vec<String>) -> Result<(), ExecuteError> {
+        copy_to_clipboard(
+            emojis.join("\n"),
+            self.output_settings.selection,
+            None,
+            self.output_settings.clipboard_backend,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::result_sink::tests::RecordingSink;
+
+    fn search(pattern: &str, config: &Config) -> Arc<RecordingSink> {
+        let sink = Arc::new(RecordingSink::default());
+        EmojiSearcher::new(config).search(pattern.to_string(), sink.clone(), 1);
+        sink
+    }
+
+    #[test]
+    fn exact_aliases_are_ranked_first() {
+        let sink = search(":wink", &Config::default());
+
+        assert_eq!(
+            sink.values(),
+            vec![Some("😉".to_string()), Some("😜".to_string())]
+        );
+    }
+
+    #[test]
+    fn aliases_match_without_being_highlighted() {
+        let sink = search(":thumbsup", &Config::default());
+        let entries = sink.entries.lock().unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].label, "+1, thumbs up");
+        assert!(entries[0].match_ranges.is_empty());
+    }
+
+    #[test]
+    fn label_matches_are_highlighted() {
+        let sink = search(":duck", &Config::default());
+        let entries = sink.entries.lock().unwrap();
+
+        assert_eq!(entries[0].label_segments(), vec![("duck", true)]);
+    }
+
+    #[test]
+    fn closing_colon_executes_the_exact_shortcode() {
+        let sink = search(":thumbsup:", &Config::default());
+
+        assert_eq!(sink.values(), vec![Some("👍".to_string())]);
+        assert_eq!(*sink.executions.lock().unwrap(), vec![false]);
+    }
+
+    #[test]
+    fn empty_pattern_returns_no_results() {
+        let sink = search(":", &Config::default());
+
+        assert!(sink.entries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn results_are_limited() {
+        let mut config = Config::default();
+        config.searchers.emoji.result_limit = Some(1);
+
+        let sink = search(":wink", &config);
+
+        assert_eq!(sink.values(), vec![Some("😉".to_string())]);
+    }
+
+    #[test]
+    fn detail_lists_code_points_and_sorted_shortcodes() {
+        assert_eq!(
+            EmojiSearcher::build_detail("👍"),
+            "U+1F44D  :+1: :thumbsup:"
+        );
     }
 }
@@ -0,0 +1,66 @@
+use std::{error, fmt, io};
+
+use crate::helpers::{clipboard_management::ClipboardError, output_management::OutputError};
+
+/// Error raised when executing a search result entry; it's meant to be displayed to the user.
+#[derive(Debug)]
+pub enum ExecuteError {
+    /// The text couldn't be output (copied, typed or pasted).
+    Output(OutputError),
+    /// The clipboard couldn't be accessed (e.g. for reading it into a snippet).
+    Clipboard(ClipboardError),
+    /// The program opening the entry (first field) couldn't be started.
+    Launch(String, io::Error),
+    /// The text entered for an action is not valid.
+    InvalidInput(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for ExecuteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Output(error) => write!(f, "{error}"),
+            Self::Clipboard(error) => write!(f, "{error}"),
+            Self::Launch(program, error) if error.kind() == io::ErrorKind::NotFound => {
+                write!(f, "Couldn't open the file: program `{program}` not found")
+            }
+            Self::Launch(program, error) => {
+                write!(
+                    f,
+                    "Couldn't open the file: `{program}` failed to start ({error})"
+                )
+            }
+            Self::InvalidInput(message) => write!(f, "{message}"),
+            Self::Io(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl error::Error for ExecuteError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Output(error) => Some(error),
+            Self::Clipboard(error) => Some(error),
+            Self::Launch(_, error) | Self::Io(error) => Some(error),
+            Self::InvalidInput(_) => None,
+        }
+    }
+}
+
+impl From<OutputError> for ExecuteError {
+    fn from(error: OutputError) -> Self {
+        Self::Output(error)
+    }
+}
+
+impl From<ClipboardError> for ExecuteError {
+    fn from(error: ClipboardError) -> Self {
+        Self::Clipboard(error)
+    }
+}
+
+impl From<io::Error> for ExecuteError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
@@ -1,50 +1,405 @@
-use std::{fs, os::unix::prelude::CommandExt, path::Path, process::Command};
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs, io,
+    ops::Range,
+    path::{self, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-use fltk::app::Sender;
 use regex::Regex;
+use serde::Deserialize;
 use walkdir::{DirEntry, WalkDir};
 
-use super::{search_result_entry::SearchResultEntry, searcher::Searcher};
+use super::{
+    action::{Action, OPEN_FOLDER_ACTION_ID},
+    execute_error::ExecuteError,
+    result_sink::ResultSink,
+    search_result_entry::{EntryIcon, SearchResultEntry, ICON_SIZE},
+    searcher::Searcher,
+    searcher_settings::searcher_settings,
+};
 use crate::{
     config::config_manager::Config,
-    gui::message_event::MessageEvent::{self, UpdateList},
-    helpers::{clipboard_management::copy_to_clipboard, filenames::map_filenames_to_short_names},
+    helpers::{
+        clipboard_management::{copy_to_clipboard, ClipboardError},
+        executables::program_in_path,
+        filenames::{map_filenames_to_short_names, path_to_file_uri},
+        icon_theme::lookup_icon,
+        mime_types::{guess_mime_type, mime_type_icon_names, query_mime_type},
+        output_management::{output_text, OutputMode, OutputSettings},
+        process_management::spawn_detached,
+        trash::{restore_file, trash_file, TrashedFile},
+    },
 };
 
 const DISALLOWED_PATH_CHARS: &str = r"[^-\w*_. /&']";
 const DISALLOWED_CHARS_MESSAGE: &str = "Only alphanum and `*_-. /&` are allowed";
 const MIN_CHARS: usize = 2;
+const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+const DEFAULT_OPENER: &str = "xdg-open";
+const DBUS_SEND_PROGRAM: &str = "dbus-send";
+// Milliseconds; the file manager may need to be started.
+const DBUS_REPLY_TIMEOUT: &str = "5000";
+const UNKNOWN_MIME_TYPE: &str = "application/octet-stream";
+
+const ACTION_OPEN: &str = "open";
+const ACTION_OPEN_WITH: &str = "open-with";
+const ACTION_OPEN_FOLDER: &str = OPEN_FOLDER_ACTION_ID;
+const ACTION_COPY_PATH: &str = "copy-path";
+const ACTION_COPY_CONTENTS: &str = "copy-contents";
+const ACTION_TRASH: &str = "trash";
+const ACTION_RENAME: &str = "rename";
+
+// File paths can't contain NUL, so the value can't clash with a search result.
+//
+const UNDO_TRASH_VALUE: &str = "\0undo-trash";
+
+// Last file moved to the trash, and the pattern of the search it was found with; the undo entry is
+// displayed in the results of that pattern. It's global, since a searcher is instantiated on each
+// search.
+//
+static LAST_TRASHED: Mutex<Option<(TrashedFile, String)>> = Mutex::new(None);
+
+// A pattern starting with `@<scope>` (followed by a space) searches in the given scope.
+//
+const SCOPE_PREFIX: char = '@';
+
+// The empty prefix handles all the patterns, so this searcher must be the last one.
+//
+searcher_settings! {
+    pub struct FileSearcherSettings {
+        default_prefix: "",
+        search_paths: Vec<String> = vec![],
+        skip_paths: Vec<String> = vec![],
+        /// Copy files (on alternate execution) as `text/uri-list`, so that file managers paste them
+        /// as files.
+        copy_as_uri_list: bool = false,
+        /// Icon theme of the file type icons (e.g. `Adwaita`, `breeze`); empty: no icons.
+        icon_theme: String = "Adwaita".to_string(),
+        /// Scope name -> scope; sorted, so that the cycling order is predictable.
+        scopes: BTreeMap<String, ScopeSettings> = BTreeMap::new(),
+        /// Extension (e.g. `pdf`) or MIME type (e.g. `image/png`, or `image/*`) -> opener command.
+        openers: BTreeMap<String, String> = BTreeMap::new(),
+    }
+}
+
+/// Named set of search paths (`[searchers.files.scopes.<name>]`); a scope replaces the search paths,
+/// and adds its skip paths to the global ones.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScopeSettings {
+    pub search_paths: Vec<String>,
+    pub skip_paths: Vec<String>,
+}
 
 pub struct FileSearcher {
     search_paths: Vec<(String, usize)>,
     skip_paths: Vec<Regex>,
     stop_search: bool,
+    settings: FileSearcherSettings,
+    output_settings: OutputSettings,
+    // Scope used when the pattern doesn't specify one; None: no scope.
+    active_scope: Option<String>,
+    last_pattern: String,
     // It's noticeably slow to instantiate once for each file skip test.
     re_is_hidden: Regex,
 }
 
 impl FileSearcher {
-    pub fn new(config: Config) -> Self {
-        let search_paths = config
-            .search_paths
-            .into_iter()
-            .map(|path| Self::process_search_path_definition(&path))
-            .collect::<Vec<_>>();
-
-        let skip_paths = config
-            .skip_paths
-            .iter()
-            .map(|path| Self::process_skip_path_definition(path))
-            .collect::<Vec<_>>();
+    pub fn new(config: &Config, active_scope: Option<String>) -> Self {
+        let settings = config.searchers.files.clone();
+        let output_settings = settings.output_settings(config);
 
         Self {
-            search_paths,
-            skip_paths,
+            search_paths: vec![],
+            skip_paths: vec![],
             stop_search: false,
+            settings,
+            output_settings,
+            active_scope,
+            last_pattern: String::new(),
             re_is_hidden: Regex::new(r"/\.[^/]+$").unwrap(),
         }
     }
 
+    /// Loads the data of the file type icons (the MIME database and the icon theme), which takes a
+    /// while; frontends can call it in a separate thread on startup, so that the first search doesn't
+    /// wait for it.
+    pub fn preload_icons(config: &Config) {
+        // Any path works, since the lookup loads both.
+        //
+        Self::new(config, None).file_icon("/");
+    }
+
+    // Returns the scope name (if any), and the pattern without the scope prefix.
+    //
+    fn split_scope(pattern: &str) -> (Option<&str>, &str) {
+        if let Some(scoped_pattern) = pattern.strip_prefix(SCOPE_PREFIX) {
+            let (scope_name, pattern) = scoped_pattern
+                .split_once(' ')
+                .unwrap_or((scoped_pattern, ""));
+
+            (Some(scope_name), pattern.trim_start())
+        } else {
+            (None, pattern)
+        }
+    }
+
+    // Starts the program detached, with the path as last argument.
+    //
+    fn launch(program: String, mut args: Vec<String>, path: String) -> Result<(), ExecuteError> {
+        args.push(path);
+
+        spawn_detached(&program, &args).map_err(|error| ExecuteError::Launch(program, error))
+    }
+
+    // Returns the program and its arguments, which are separated by whitespace (quoting is not
+    // supported); None if the command is blank.
+    //
+    fn split_command(command: &str) -> Option<(String, Vec<String>)> {
+        let mut command_parts = command.split_whitespace().map(str::to_string);
+
+        command_parts
+            .next()
+            .map(|program| (program, command_parts.collect()))
+    }
+
+    // Opens the parent directory, with the file selected, if the file manager supports the
+    // `org.freedesktop.FileManager1` interface; otherwise, the parent directory is opened via the
+    // default opener.
+    //
+    // The D-Bus reply must be waited for, since it's the only way to know whether a file manager handled
+    // the call, and it may take a while (the file manager may need to be started); for this reason, the
+    // call and the fallback run in a detached shell, rather than blocking the caller.
+    //
+    fn open_folder(filename: &str) -> Result<(), ExecuteError> {
+        let canonical_path = fs::canonicalize(filename)?;
+        let parent = canonical_path.parent().unwrap_or(&canonical_path);
+
+        // Checked upfront, since the errors of the detached shell can't be reported.
+        //
+        if !program_in_path(DEFAULT_OPENER) {
+            return Err(ExecuteError::Launch(
+                DEFAULT_OPENER.to_string(),
+                io::ErrorKind::NotFound.into(),
+            ));
+        }
+
+        // URIs are percent-encoded, so they don't contain the array separator (comma). The last
+        // argument of the call is the startup id, which is not available.
+        //
+        let items_arg = format!("array:string:{}", path_to_file_uri(&canonical_path));
+
+        let dbus_command = [
+            DBUS_SEND_PROGRAM,
+            "--session",
+            "--print-reply",
+            &format!("--reply-timeout={DBUS_REPLY_TIMEOUT}"),
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+            r#""$1""#,
+            "string:",
+        ]
+        .join(" ");
+
+        let script = format!(r#"{dbus_command} >/dev/null 2>&1 || {DEFAULT_OPENER} "$2""#);
+
+        // The arguments are passed as positional parameters, so that they don't need to be escaped.
+        //
+        let script_args = [
+            OsStr::new("-c"),
+            OsStr::new(&script),
+            OsStr::new("sh"),
+            OsStr::new(&items_arg),
+            parent.as_os_str(),
+        ];
+
+        spawn_detached("sh", &script_args)
+            .map_err(|error| ExecuteError::Launch("sh".to_string(), error))
+    }
+
+    // Copies regardless of the output mode, since the action is explicitly a copy.
+    //
+    fn copy_text(&self, text: String) -> Result<(), ExecuteError> {
+        copy_to_clipboard(
+            text,
+            self.output_settings.selection,
+            None,
+            self.output_settings.clipboard_backend,
+        )?;
+
+        Ok(())
+    }
+
+    // The list format requires CRLF line terminators. Backends that can't set the target type would
+    // paste the URIs as text, so the plain paths are copied instead.
+    //
+    fn copy_uri_list(&self, canonical_paths: &[PathBuf]) -> Result<(), ExecuteError> {
+        let uri_list = canonical_paths
+            .iter()
+            .map(|canonical_path| format!("{}\r\n", path_to_file_uri(canonical_path)))
+            .collect::<String>();
+
+        match copy_to_clipboard(
+            uri_list,
+            self.output_settings.selection,
+            Some(URI_LIST_MIME_TYPE),
+            self.output_settings.clipboard_backend,
+        ) {
+            Err(ClipboardError::Unsupported(_)) => {
+                self.copy_text(Self::paths_text(canonical_paths))
+            }
+            result => Ok(result?),
+        }
+    }
+
+    // One per line. Non-UTF-8 paths (valid on Linux) can't be output as text, so the invalid bytes are
+    // replaced; URIs are percent-encoded instead, so they're not affected.
+    //
+    fn paths_text(paths: &[PathBuf]) -> String {
+        paths
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // The path is not canonicalized, since symlinks must be trashed, rather than their target.
+    //
+    fn trash(&self, filename: &str) -> Result<(), ExecuteError> {
+        let trashed_file = trash_file(&path::absolute(filename)?)?;
+
+        *LAST_TRASHED.lock().unwrap() = Some((trashed_file, self.last_pattern.clone()));
+
+        Ok(())
+    }
+
+    fn undo_trash() -> Result<(), ExecuteError> {
+        let mut last_trashed = LAST_TRASHED.lock().unwrap();
+
+        if let Some((trashed_file, _)) = last_trashed.as_ref() {
+            restore_file(trashed_file)?;
+            *last_trashed = None;
+        }
+
+        Ok(())
+    }
+
+    fn undo_trash_entry(&self, search_id: u32) -> Option<SearchResultEntry> {
+        let last_trashed = LAST_TRASHED.lock().unwrap();
+        let (trashed_file, pattern) = last_trashed.as_ref()?;
+
+        if *pattern != self.last_pattern {
+            return None;
+        }
+
+        let basename = trashed_file
+            .original_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+
+        let entry = SearchResultEntry::new(
+            None,
+            format!("Undo moving `{basename}` to the trash"),
+            Some(UNDO_TRASH_VALUE.to_string()),
+            search_id,
+            true,
+        );
+
+        Some(entry.with_detail(trashed_file.original_path.display().to_string()))
+    }
+
+    // The file is renamed within its directory.
+    //
+    fn rename(filename: &str, new_name: &str) -> Result<(), ExecuteError> {
+        let new_name = new_name.trim();
+
+        if new_name.is_empty() || new_name.contains('/') || new_name == "." || new_name == ".." {
+            return Err(ExecuteError::InvalidInput(format!(
+                "Invalid file name: `{new_name}`"
+            )));
+        }
+
+        let path = Path::new(filename);
+        let new_path = path.with_file_name(new_name);
+
+        if new_path.exists() {
+            return Err(ExecuteError::InvalidInput(format!(
+                "`{new_name}` already exists"
+            )));
+        }
+
+        fs::rename(path, new_path)?;
+
+        Ok(())
+    }
+
+    // Returns the opener program, and its arguments (the file is appended by the caller).
+    //
+    // Precedence: extension, MIME type, MIME type wildcard, default opener. The MIME type is queried
+    // only if there are MIME type openers, since it may require reading the file.
+    //
+    fn opener_command(&self, filename: &str) -> (String, Vec<String>) {
+        let openers = &self.settings.openers;
+
+        let extension = Path::new(filename).extension().and_then(OsStr::to_str);
+
+        let mut opener = extension.and_then(|extension| {
+            openers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(extension))
+                .map(|(_, opener)| opener)
+        });
+
+        if opener.is_none() && openers.keys().any(|key| key.contains('/')) {
+            if let Some(mime_type) = query_mime_type(Path::new(filename)) {
+                let (media_type, _) = mime_type.split_once('/').unwrap_or((&mime_type, ""));
+
+                opener = openers
+                    .get(&mime_type)
+                    .or_else(|| openers.get(&format!("{media_type}/*")));
+            }
+        }
+
+        opener
+            .and_then(|opener| Self::split_command(opener))
+            .unwrap_or_else(|| (DEFAULT_OPENER.to_string(), vec![]))
+    }
+
+    // Sets the search/skip paths; None uses the global ones.
+    //
+    fn apply_scope(&mut self, scope_name: Option<&str>) -> Result<(), String> {
+        let mut search_path_definitions = &self.settings.search_paths;
+        let mut skip_path_definitions = self.settings.skip_paths.clone();
+
+        if let Some(scope_name) = scope_name {
+            let scope = self
+                .settings
+                .scopes
+                .get(scope_name)
+                .ok_or_else(|| format!("Unknown scope: `{scope_name}`"))?;
+
+            search_path_definitions = &scope.search_paths;
+            skip_path_definitions.extend(scope.skip_paths.iter().cloned());
+        }
+
+        self.search_paths = search_path_definitions
+            .iter()
+            .map(|path| Self::process_search_path_definition(path))
+            .collect();
+
+        self.skip_paths = skip_path_definitions
+            .iter()
+            .map(|path| Self::process_skip_path_definition(path))
+            .collect();
+
+        Ok(())
+    }
+
     fn process_search_path_definition(mut path: &str) -> (String, usize) {
         let mut depth = 255;
 
@@ -117,6 +472,35 @@ impl FileSearcher {
             .any(|skip_re| skip_re.is_match(&fullname))
     }
 
+    // Icon of the file type; None if no icon is found. The type is guessed from the filename, since
+    // reading the content of all the results would be too slow; unknown types get the generic icon.
+    //
+    fn file_icon(&self, filename: &str) -> Option<EntryIcon> {
+        let theme_name = &self.settings.icon_theme;
+
+        if theme_name.is_empty() {
+            return None;
+        }
+
+        let mime_type =
+            guess_mime_type(Path::new(filename)).unwrap_or_else(|| UNKNOWN_MIME_TYPE.to_string());
+
+        lookup_icon(theme_name, ICON_SIZE, &mime_type_icon_names(&mime_type)).map(EntryIcon::Path)
+    }
+
+    // The pattern is matched against the basename, which is the last component of the label (the
+    // parents are added only to disambiguate).
+    //
+    fn match_ranges(label: &str, re_pattern: &Regex) -> Vec<Range<usize>> {
+        let basename_start = label.rfind('/').map_or(0, |index| index + 1);
+
+        re_pattern
+            .find_iter(&label[basename_start..])
+            .filter(|found| !found.is_empty())
+            .map(|found| basename_start + found.start()..basename_start + found.end())
+            .collect()
+    }
+
     fn include_entry(entry: &DirEntry, re_pattern: &Regex) -> Option<String> {
         let path = entry.path();
         let filename = path.file_name().unwrap().to_str().unwrap();
@@ -130,14 +514,27 @@ impl FileSearcher {
 }
 
 impl Searcher for FileSearcher {
-    fn handles(&self, _pattern: &str) -> bool {
-        true
+    fn handles(&self, pattern: &str) -> bool {
+        self.settings.strip_prefix(pattern).is_some()
     }
 
-    fn search(&mut self, pattern: String, sender: Sender<MessageEvent>, search_id: u32) {
+    fn search(&mut self, pattern: String, result_sink: Arc<dyn ResultSink>, search_id: u32) {
+        self.last_pattern = pattern.clone();
+
+        let pattern = self
+            .settings
+            .strip_prefix(&pattern)
+            .unwrap_or(&pattern)
+            .to_string();
+
+        let (scope_name, pattern) = Self::split_scope(&pattern);
+        let scope_name = scope_name
+            .map(|scope_name| scope_name.to_string())
+            .or_else(|| self.active_scope.clone());
+
         let re_disallowed_chars = Regex::new(DISALLOWED_PATH_CHARS).unwrap();
 
-        if re_disallowed_chars.is_match(&pattern) {
+        if re_disallowed_chars.is_match(pattern) {
             let processed_result = vec![SearchResultEntry::new(
                 None,
                 DISALLOWED_CHARS_MESSAGE.into(),
@@ -146,7 +543,7 @@ impl Searcher for FileSearcher {
                 false,
             )];
 
-            sender.send(UpdateList(processed_result));
+            result_sink.update_list(processed_result);
             return;
         }
 
@@ -154,6 +551,16 @@ impl Searcher for FileSearcher {
             return;
         }
 
+        // Applied after the checks, so that no error is displayed while the scope name is typed.
+        //
+        if let Err(error) = self.apply_scope(scope_name.as_deref()) {
+            let processed_result =
+                vec![SearchResultEntry::new(None, error, None, search_id, false)];
+
+            result_sink.update_list(processed_result);
+            return;
+        }
+
         let mut pattern = pattern.replace('.', r"\.").replace('*', ".*");
         pattern = format!("(?i){}", pattern);
         let re_pattern = Regex::new(&pattern).unwrap();
@@ -194,36 +601,346 @@ impl Searcher for FileSearcher {
 
         let filename_labels = map_filenames_to_short_names(matching_fullnames);
 
-        let processed_result = filename_labels
-            .into_iter()
-            .map(|(label, fullname)| {
-                SearchResultEntry::new(None, label, Some(fullname), search_id, true)
-            })
+        // The results are limited before looking up the icons, since the lookup accesses the filesystem.
+        //
+        let filename_labels = self
+            .settings
+            .limit_results(filename_labels.into_iter().collect());
+
+        let processed_result = filename_labels.into_iter().map(|(label, fullname)| {
+            let match_ranges = Self::match_ranges(&label, &re_pattern);
+            let icon = self.file_icon(&fullname);
+
+            SearchResultEntry::new(icon, label, Some(fullname.clone()), search_id, true)
+                .with_detail(fullname)
+                .with_match_ranges(match_ranges)
+        });
+
+        // The undo entry goes last, so that executing without a selection (which executes the first
+        // entry) opens the top match rather than restoring the file.
+        //
+        let search_result = processed_result
+            .chain(self.undo_trash_entry(search_id))
             .collect();
 
-        sender.send(UpdateList(processed_result));
+        result_sink.update_list(search_result);
     }
 
-    fn execute(&self, filename: String) {
-        // This is Unix-specific, in two ways:
-        //
-        // - it uses xdg-open
-        // - exec() will replace the pm-spotlight image with the executed program (unless it errors)
-        //
-        // this is currently fine.
+    // The opener is detached, so that pm-spotlight can stay resident.
+    //
+    fn execute(&self, filename: String) -> Result<(), ExecuteError> {
+        if filename == UNDO_TRASH_VALUE {
+            return Self::undo_trash();
+        }
+
+        let (program, args) = self.opener_command(&filename);
+
+        Self::launch(program, args, filename)
+    }
+
+    fn alt_execute(&self, filename: String) -> Result<bool, ExecuteError> {
+        if filename == UNDO_TRASH_VALUE {
+            return Ok(false);
+        }
+
+        let canonical_paths = [fs::canonicalize(filename)?];
+
+        // URIs are meaningful only when copying; when typing/pasting, the path is output as text.
         //
-        let _ = Command::new("xdg-open").args([filename]).exec();
+        if self.settings.copy_as_uri_list
+            && matches!(self.output_settings.mode, OutputMode::Clipboard)
+        {
+            self.copy_uri_list(&canonical_paths)?;
+        } else {
+            output_text(Self::paths_text(&canonical_paths), 0, self.output_settings)?;
+        }
+
+        Ok(true)
     }
 
-    fn alt_execute(&self, filename: String) -> bool {
-        let canonical_path = fs::canonicalize(filename)
-            .unwrap()
+    // Multiple paths are always copied, one per line, regardless of the output mode.
+    //
+    fn alt_execute_batch(&self, filenames: Vec<String>) -> Result<bool, ExecuteError> {
+        let canonical_paths = filenames
+            .into_iter()
+            .filter(|filename| filename != UNDO_TRASH_VALUE)
+            .map(fs::canonicalize)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if canonical_paths.is_empty() {
+            return Ok(false);
+        }
+
+        if self.settings.copy_as_uri_list {
+            self.copy_uri_list(&canonical_paths)?;
+        } else {
+            self.copy_text(Self::paths_text(&canonical_paths))?;
+        }
+
+        Ok(true)
+    }
+
+    fn preview_path(&self, entry: &SearchResultEntry) -> Option<PathBuf> {
+        let filename = entry.value.as_deref().unwrap_or(&entry.label);
+
+        (filename != UNDO_TRASH_VALUE).then(|| PathBuf::from(filename))
+    }
+
+    fn actions(&self, entry: &SearchResultEntry) -> Vec<Action> {
+        let filename = entry.value.as_deref().unwrap_or(&entry.label);
+
+        if filename == UNDO_TRASH_VALUE {
+            return vec![];
+        }
+        let basename = Path::new(filename)
+            .file_name()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default();
+
+        let mut actions = vec![
+            Action::new(ACTION_OPEN, "Open"),
+            Action::new(ACTION_OPEN_WITH, "Open with…").with_input("Program:", ""),
+            Action::new(ACTION_OPEN_FOLDER, "Open containing folder"),
+            Action::new(ACTION_COPY_PATH, "Copy path"),
+        ];
+
+        if Path::new(filename).is_file() {
+            actions.push(Action::new(ACTION_COPY_CONTENTS, "Copy contents"));
+        }
+
+        actions.extend([
+            Action::new(ACTION_TRASH, "Move to trash")
+                .with_confirmation(format!("Move `{basename}` to the trash?"))
+                .with_refresh(),
+            Action::new(ACTION_RENAME, "Rename")
+                .with_input("New name:", basename)
+                .with_refresh(),
+        ]);
+
+        actions
+    }
+
+    fn execute_action(
+        &self,
+        action_id: &str,
+        filename: String,
+        input: Option<String>,
+    ) -> Result<(), ExecuteError> {
+        let input = input.unwrap_or_default();
+
+        match action_id {
+            ACTION_OPEN => self.execute(filename),
+            ACTION_OPEN_WITH => {
+                let (program, args) = Self::split_command(&input).ok_or_else(|| {
+                    ExecuteError::InvalidInput("No program specified".to_string())
+                })?;
+
+                Self::launch(program, args, filename)
+            }
+            ACTION_OPEN_FOLDER => Self::open_folder(&filename),
+            ACTION_COPY_PATH => {
+                let canonical_paths = [fs::canonicalize(filename)?];
+
+                self.copy_text(Self::paths_text(&canonical_paths))
+            }
+            ACTION_COPY_CONTENTS => self.copy_text(fs::read_to_string(filename)?),
+            ACTION_TRASH => self.trash(&filename),
+            ACTION_RENAME => Self::rename(&filename, &input),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+    use crate::search::result_sink::tests::RecordingSink;
+
+    // Creates a directory tree specific to the test, with empty files at the given relative paths.
+    //
+    fn create_test_tree(test_name: &str, relative_paths: &[&str]) -> PathBuf {
+        let test_dir =
+            env::temp_dir().join(format!("pm-spotlight-test-{}-{test_name}", process::id()));
+
+        for relative_path in relative_paths {
+            let path = test_dir.join(relative_path);
+
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        test_dir
+    }
+
+    fn test_config(search_dir: &Path) -> Config {
+        let mut config = Config::default();
+
+        config.searchers.files.search_paths = vec![search_dir.to_str().unwrap().to_string()];
+        config.searchers.files.icon_theme = String::new();
+
+        config
+    }
+
+    fn search(pattern: &str, config: &Config) -> Arc<RecordingSink> {
+        let sink = Arc::new(RecordingSink::default());
+        FileSearcher::new(config, None).search(pattern.to_string(), sink.clone(), 1);
+        sink
+    }
+
+    fn sorted_labels(sink: &RecordingSink) -> Vec<String> {
+        let mut labels = sink.labels();
+        labels.sort();
+        labels
+    }
+
+    #[test]
+    fn finds_matching_files_excluding_hidden_ones() {
+        let test_dir = create_test_tree(
+            "finds_matching_files",
+            &[
+                "report.pdf",
+                "notes.txt",
+                "sub/report.txt",
+                ".hidden/report.md",
+            ],
+        );
+
+        let sink = search("report", &test_config(&test_dir));
+
+        assert_eq!(sorted_labels(&sink), vec!["report.pdf", "report.txt"]);
+
+        let entries = sink.entries.lock().unwrap();
+        let entry = entries
+            .iter()
+            .find(|entry| entry.label == "report.txt")
+            .unwrap();
+        let fullname = test_dir
+            .join("sub/report.txt")
             .to_str()
             .unwrap()
             .to_string();
 
-        copy_to_clipboard(canonical_path);
+        assert_eq!(entry.value.as_deref(), Some(fullname.as_str()));
+        assert_eq!(entry.detail.as_deref(), Some(fullname.as_str()));
+        assert_eq!(entry.match_ranges, vec![0..6]);
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn wildcards_and_skip_paths_are_applied() {
+        let test_dir = create_test_tree(
+            "wildcards_and_skip_paths",
+            &["report.pdf", "summary.pdf", "archive/old.pdf"],
+        );
+
+        let mut config = test_config(&test_dir);
+        config.searchers.files.skip_paths = vec![format!("{}/arch*", test_dir.display())];
+
+        let sink = search("*.pdf", &config);
+
+        assert_eq!(sorted_labels(&sink), vec!["report.pdf", "summary.pdf"]);
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn results_are_limited() {
+        let test_dir = create_test_tree("results_are_limited", &["report.pdf", "report.txt"]);
+
+        let mut config = test_config(&test_dir);
+        config.searchers.files.result_limit = Some(1);
+
+        let sink = search("report", &config);
+
+        assert_eq!(sink.labels().len(), 1);
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported() {
+        let config = test_config(Path::new("/nonexistent"));
+
+        let sink = search("re?port", &config);
+        let entries = sink.entries.lock().unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].label, DISALLOWED_CHARS_MESSAGE);
+        assert!(!entries[0].valid);
+    }
+
+    #[test]
+    fn short_patterns_are_not_searched() {
+        let sink = search("r", &test_config(Path::new("/nonexistent")));
+
+        assert!(sink.entries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn scopes_replace_the_search_paths() {
+        let test_dir = create_test_tree("scopes", &["global/report.pdf", "work/report.txt"]);
+
+        let mut config = test_config(&test_dir.join("global"));
+        config.searchers.files.scopes.insert(
+            "work".to_string(),
+            ScopeSettings {
+                search_paths: vec![test_dir.join("work").to_str().unwrap().to_string()],
+                skip_paths: vec![],
+            },
+        );
+
+        assert_eq!(search("report", &config).labels(), vec!["report.pdf"]);
+        assert_eq!(search("@work report", &config).labels(), vec!["report.txt"]);
+
+        let sink = search("@play report", &config);
+        let entries = sink.entries.lock().unwrap();
+
+        assert_eq!(entries[0].label, "Unknown scope: `play`");
+        assert!(!entries[0].valid);
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+
+    #[test]
+    fn undo_trash_entry_follows_the_results() {
+        let test_dir = create_test_tree("undo_trash_entry", &["ordering.txt"]);
+
+        *LAST_TRASHED.lock().unwrap() = Some((
+            TrashedFile {
+                original_path: test_dir.join("ordering.pdf"),
+                trashed_path: PathBuf::new(),
+                info_path: PathBuf::new(),
+            },
+            "ordering".to_string(),
+        ));
+
+        let values = search("ordering", &test_config(&test_dir)).values();
+
+        *LAST_TRASHED.lock().unwrap() = None;
+
+        assert_eq!(
+            values,
+            vec![
+                Some(test_dir.join("ordering.txt").to_str().unwrap().to_string()),
+                Some(UNDO_TRASH_VALUE.to_string()),
+            ]
+        );
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
 
-        std::process::exit(0);
+    #[test]
+    fn search_path_depth_is_parsed() {
+        assert_eq!(
+            FileSearcher::process_search_path_definition("/data{2}"),
+            ("/data".to_string(), 2)
+        );
+        assert_eq!(
+            FileSearcher::process_search_path_definition("/data"),
+            ("/data".to_string(), 255)
+        );
     }
 }
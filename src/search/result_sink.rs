@@ -0,0 +1,59 @@
+use super::search_result_entry::SearchResultEntry;
+
+/// Receiver of the search results; it's implemented by the frontends (typically, by forwarding to their
+/// event loop), so that the search layer doesn't depend on any of them.
+///
+/// Sinks are shared, since searches can run in separate threads.
+pub trait ResultSink: Send + Sync {
+    /// Appends the entries to the current results.
+    fn update_list(&self, entries: Vec<SearchResultEntry>);
+
+    /// Requests the execution of the first/selected entry, e.g. on an exact match.
+    /// False: normal; true: alternate.
+    fn execute_entry(&self, alternate: bool);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // Records the calls, so that the tests can inspect them.
+    //
+    #[derive(Default)]
+    pub struct RecordingSink {
+        pub entries: Mutex<Vec<SearchResultEntry>>,
+        pub executions: Mutex<Vec<bool>>,
+    }
+
+    impl RecordingSink {
+        pub fn labels(&self) -> Vec<String> {
+            self.entries
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|entry| entry.label.clone())
+                .collect()
+        }
+
+        pub fn values(&self) -> Vec<Option<String>> {
+            self.entries
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|entry| entry.value.clone())
+                .collect()
+        }
+    }
+
+    impl ResultSink for RecordingSink {
+        fn update_list(&self, entries: Vec<SearchResultEntry>) {
+            self.entries.lock().unwrap().extend(entries);
+        }
+
+        fn execute_entry(&self, alternate: bool) {
+            self.executions.lock().unwrap().push(alternate);
+        }
+    }
+}
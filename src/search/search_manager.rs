@@ -1,15 +1,74 @@
-use fltk::app::Sender;
+use std::{error, fmt, iter, path::PathBuf, str::FromStr, sync::Arc};
 
-use crate::{config::config_manager::Config, gui::message_event::MessageEvent};
+use crate::config::config_manager::Config;
 
+use super::action::Action;
 use super::emoji_searcher::EmojiSearcher;
+use super::execute_error::ExecuteError;
 #[cfg(target_os = "linux")]
 use super::file_searcher::FileSearcher;
+use super::result_sink::ResultSink;
+use super::search_result_entry::SearchResultEntry;
 use super::searcher::Searcher;
+use super::snippet_searcher::SnippetSearcher;
+use super::stdin_searcher::StdinSearcher;
 
+/// Bundled searchers; the file searcher is available only on Linux.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SearcherName {
+    Files,
+    Emoji,
+    Snippets,
+}
+
+impl SearcherName {
+    /// Names accepted by [`FromStr`], in the same order as the variants.
+    pub const NAMES: &'static [&'static str] = &["files", "emoji", "snippets"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Files => "files",
+            Self::Emoji => "emoji",
+            Self::Snippets => "snippets",
+        }
+    }
+}
+
+impl FromStr for SearcherName {
+    type Err = UnknownSearcherError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "files" => Ok(Self::Files),
+            "emoji" => Ok(Self::Emoji),
+            "snippets" => Ok(Self::Snippets),
+            _ => Err(UnknownSearcherError(name.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownSearcherError(pub String);
+
+impl fmt::Display for UnknownSearcherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown searcher: `{}`", self.0)
+    }
+}
+
+impl error::Error for UnknownSearcherError {}
+
+/// Entry point of the search layer: dispatches the patterns to the searchers, and executes the
+/// selected entries via the searcher that produced them.
 pub struct SearchManager {
     config: Config,
+    // If set, all the patterns are sent to this searcher, regardless of the prefix.
+    forced_searcher: Option<SearcherName>,
+    // If set (dmenu mode), all the patterns are sent to the stdin searcher.
+    stdin_items: Option<Arc<Vec<String>>>,
     current_searcher: Option<Box<dyn Searcher>>,
+    // File searcher scope used when the pattern doesn't specify one.
+    active_scope: Option<String>,
     // This type performs dumb id generation, but no checks. The reason is that checks must be performed
     // by the App type (e.g. display or not the entries sent from a search), so it's cleaner to perform
     // all of them there.
@@ -18,15 +77,60 @@ pub struct SearchManager {
 }
 
 impl SearchManager {
-    pub fn new(config: Config) -> Self {
+    /// If `forced_searcher` is set, all the patterns are sent to it, regardless of the prefix.
+    pub fn new(config: Config, forced_searcher: Option<SearcherName>) -> Self {
         Self {
             config,
+            forced_searcher,
+            stdin_items: None,
             current_searcher: None,
+            active_scope: None,
             current_search_id: 0,
         }
     }
 
-    pub fn search(&mut self, pattern: String, sender: Sender<MessageEvent>) -> u32 {
+    /// Applies to the searches started afterwards.
+    pub fn update_config(&mut self, config: Config) {
+        self.config = config;
+
+        // The active scope may have been removed.
+        //
+        if !self.scope_names().contains(&self.active_scope) {
+            self.active_scope = None;
+        }
+    }
+
+    /// Configuration of the searches started afterwards.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Switches to dmenu mode: all the patterns are matched against the given items.
+    pub fn set_stdin_items(&mut self, items: Vec<String>) {
+        self.stdin_items = Some(Arc::new(items));
+    }
+
+    /// File searcher scope used when the pattern doesn't specify one. The frontends display it, since
+    /// it affects the results without being visible otherwise.
+    pub fn active_scope(&self) -> Option<&str> {
+        self.active_scope.as_deref()
+    }
+
+    /// Cycles through the scopes, in name order, and no scope (after the last one).
+    pub fn cycle_scope(&mut self) {
+        let scope_names = self.scope_names();
+        let current_index = scope_names
+            .iter()
+            .position(|scope_name| *scope_name == self.active_scope)
+            .unwrap_or_default();
+
+        self.active_scope = scope_names[(current_index + 1) % scope_names.len()].clone();
+    }
+
+    /// Stops the current search, if any, and starts a new one; returns its id, which is set in the
+    /// entries sent to the sink. Entries with a different id belong to previous searches, and must be
+    /// discarded by the caller.
+    pub fn search(&mut self, pattern: String, result_sink: Arc<dyn ResultSink>) -> u32 {
         // Increase anyway. If no searchers are found, it's still meaningful that other messages should
         // be ignored.
         //
@@ -39,38 +143,251 @@ impl SearchManager {
         self.current_searcher = self.find_searcher(&pattern);
 
         if let Some(searcher) = &mut self.current_searcher {
-            searcher.search(pattern, sender, self.current_search_id);
+            searcher.search(pattern, result_sink, self.current_search_id);
         }
 
         self.current_search_id
     }
 
-    pub fn execute(&mut self, value: String) {
+    /// Executes the value of an entry of the current search.
+    pub fn execute(&mut self, value: String) -> Result<(), ExecuteError> {
         if let Some(searcher) = &mut self.current_searcher {
             searcher.execute(value)
+        } else {
+            Ok(())
         }
     }
 
-    pub fn alt_execute(&mut self, value: String) -> bool {
+    /// Like [`Self::execute`], in the alternate mode; returns false if the searcher doesn't support it.
+    pub fn alt_execute(&mut self, value: String) -> Result<bool, ExecuteError> {
         if let Some(searcher) = &mut self.current_searcher {
             searcher.alt_execute(value)
         } else {
-            false
+            Ok(false)
+        }
+    }
+
+    /// Executes the values of multiple entries of the current search.
+    pub fn execute_batch(&mut self, values: Vec<String>) -> Result<(), ExecuteError> {
+        if let Some(searcher) = &mut self.current_searcher {
+            searcher.execute_batch(values)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Like [`Self::execute_batch`], in the alternate mode; returns false if the searcher doesn't
+    /// support it.
+    pub fn alt_execute_batch(&mut self, values: Vec<String>) -> Result<bool, ExecuteError> {
+        if let Some(searcher) = &mut self.current_searcher {
+            searcher.alt_execute_batch(values)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Actions available for an entry of the current search.
+    pub fn actions(&self, entry: &SearchResultEntry) -> Vec<Action> {
+        if let Some(searcher) = &self.current_searcher {
+            searcher.actions(entry)
+        } else {
+            vec![]
+        }
+    }
+
+    /// Performs an action returned by [`Self::actions`], on the value of the entry.
+    pub fn execute_action(
+        &mut self,
+        action: &Action,
+        value: String,
+        input: Option<String>,
+    ) -> Result<(), ExecuteError> {
+        if let Some(searcher) = &mut self.current_searcher {
+            searcher.execute_action(action.id, value, input)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// File to preview for an entry of the current search, if any.
+    pub fn preview_path(&self, entry: &SearchResultEntry) -> Option<PathBuf> {
+        if let Some(searcher) = &self.current_searcher {
+            searcher.preview_path(entry)
+        } else {
+            None
         }
     }
 
     fn find_searcher(&self, pattern: &str) -> Option<Box<dyn Searcher>> {
-        // WATCH OUT!! The ordering matters - specialized searchers must go first, since the file always
-        // handles the pattern, and prevents the following ones from running.
+        if let Some(items) = &self.stdin_items {
+            return Some(Box::new(StdinSearcher::new(items.clone())));
+        }
+
+        if let Some(searcher_name) = self.forced_searcher {
+            return self.build_searcher(searcher_name);
+        }
+
+        // WATCH OUT!! The ordering matters - specialized searchers must go first, since the file
+        // searcher (with the default, empty, prefix) handles any pattern, and prevents the following
+        // ones from running.
         //
-        let searchers: Vec<Box<dyn Searcher>> = vec![
-            Box::new(EmojiSearcher::new()),
+        [
+            SearcherName::Emoji,
+            SearcherName::Snippets,
+            SearcherName::Files,
+        ]
+        .into_iter()
+        .filter(|searcher_name| self.searcher_enabled(*searcher_name))
+        .filter_map(|searcher_name| self.build_searcher(searcher_name))
+        .find(|searcher| searcher.handles(pattern))
+    }
+
+    fn searcher_enabled(&self, searcher_name: SearcherName) -> bool {
+        let searchers_config = &self.config.searchers;
+
+        match searcher_name {
+            SearcherName::Emoji => searchers_config.emoji.enabled,
+            SearcherName::Snippets => searchers_config.snippets.enabled,
             #[cfg(target_os = "linux")]
-            Box::new(FileSearcher::new(self.config.clone())),
-        ];
+            SearcherName::Files => searchers_config.files.enabled,
+            #[cfg(not(target_os = "linux"))]
+            SearcherName::Files => false,
+        }
+    }
+
+    // Returns None if the searcher is not supported on the platform.
+    //
+    fn build_searcher(&self, searcher_name: SearcherName) -> Option<Box<dyn Searcher>> {
+        match searcher_name {
+            SearcherName::Emoji => Some(Box::new(EmojiSearcher::new(&self.config))),
+            SearcherName::Snippets => Some(Box::new(SnippetSearcher::new(&self.config))),
+            #[cfg(target_os = "linux")]
+            SearcherName::Files => Some(Box::new(FileSearcher::new(
+                &self.config,
+                self.active_scope.clone(),
+            ))),
+            #[cfg(not(target_os = "linux"))]
+            SearcherName::Files => None,
+        }
+    }
+
+    // Includes the "no scope" option, as first entry.
+    //
+    fn scope_names(&self) -> Vec<Option<String>> {
+        #[cfg(target_os = "linux")]
+        let configured_scopes: Vec<String> =
+            self.config.searchers.files.scopes.keys().cloned().collect();
+        #[cfg(not(target_os = "linux"))]
+        let configured_scopes: Vec<String> = vec![];
+
+        iter::once(None)
+            .chain(configured_scopes.into_iter().map(Some))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::result_sink::tests::RecordingSink;
+
+    #[test]
+    fn search_ids_increase_and_tag_the_entries() {
+        let mut search_manager = SearchManager::new(Config::default(), None);
+
+        for expected_search_id in 1..=2 {
+            let sink = Arc::new(RecordingSink::default());
+            let search_id = search_manager.search(":duck".to_string(), sink.clone());
+
+            assert_eq!(search_id, expected_search_id);
+
+            let entries = sink.entries.lock().unwrap();
+
+            assert!(!entries.is_empty());
+            assert!(entries.iter().all(|entry| entry.search_id == search_id));
+        }
+    }
+
+    #[test]
+    fn dispatches_by_prefix() {
+        let mut search_manager = SearchManager::new(Config::default(), None);
+
+        let sink = Arc::new(RecordingSink::default());
+        search_manager.search(":duck".to_string(), sink.clone());
+        assert_eq!(sink.values(), vec![Some("🦆".to_string())]);
+
+        let sink = Arc::new(RecordingSink::default());
+        search_manager.search(";tableflip".to_string(), sink.clone());
+        assert!(sink.values().contains(&Some("(╯°□°)╯︵ ┻━┻".to_string())));
+    }
+
+    #[test]
+    fn forced_searcher_doesnt_require_the_prefix() {
+        let mut search_manager =
+            SearchManager::new(Config::default(), Some(SearcherName::Snippets));
+
+        let sink = Arc::new(RecordingSink::default());
+        search_manager.search("shrug".to_string(), sink.clone());
+
+        assert!(sink.values().contains(&Some(r"¯\_(ツ)_/¯".to_string())));
+    }
+
+    #[test]
+    fn disabled_searchers_are_skipped() {
+        let mut config = Config::default();
+        config.searchers.emoji.enabled = false;
+
+        let mut search_manager = SearchManager::new(config, None);
+
+        let sink = Arc::new(RecordingSink::default());
+        search_manager.search(":duck".to_string(), sink.clone());
+
+        assert!(!sink.values().contains(&Some("🦆".to_string())));
+    }
+
+    #[test]
+    fn stdin_items_take_precedence_over_the_prefixes() {
+        let mut search_manager = SearchManager::new(Config::default(), None);
+        search_manager.set_stdin_items(vec![":duck".to_string(), "goose".to_string()]);
+
+        let sink = Arc::new(RecordingSink::default());
+        search_manager.search(":du".to_string(), sink.clone());
+
+        assert_eq!(sink.labels(), vec![":duck"]);
+    }
+
+    #[test]
+    fn execution_without_a_search_does_nothing() {
+        let mut search_manager = SearchManager::new(Config::default(), None);
+
+        assert!(search_manager.execute("value".to_string()).is_ok());
+        assert!(!search_manager.alt_execute("value".to_string()).unwrap());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cycle_scope_includes_no_scope() {
+        let mut config = Config::default();
+
+        for scope_name in ["work", "home"] {
+            config
+                .searchers
+                .files
+                .scopes
+                .insert(scope_name.to_string(), Default::default());
+        }
+
+        let mut search_manager = SearchManager::new(config, None);
+        let mut active_scopes = vec![];
+
+        for _ in 0..3 {
+            search_manager.cycle_scope();
+            active_scopes.push(search_manager.active_scope().map(str::to_string));
+        }
 
-        searchers
-            .into_iter()
-            .find(|searcher| searcher.handles(pattern))
+        assert_eq!(
+            active_scopes,
+            vec![Some("home".to_string()), Some("work".to_string()), None]
+        );
     }
 }
@@ -1,24 +1,46 @@
-use fltk::image::SharedImage;
+use std::{ops::Range, path::PathBuf};
 
+/// Size (width and height, in pixels) the icons are looked up at and displayed at; frontends scale the
+/// larger images down.
+pub const ICON_SIZE: u32 = 32;
+
+/// Frontend-neutral icon; frontends convert it to their image type (or ignore it).
+#[derive(Clone)]
+pub enum EntryIcon {
+    /// PNG image data.
+    Png(&'static [u8]),
+    /// Image file, in any format supported by the frontend.
+    Path(PathBuf),
+}
+
+/// Result of a search, as sent to the [`ResultSink`](super::result_sink::ResultSink).
 #[derive(Clone)]
 pub struct SearchResultEntry {
-    pub icon: Option<SharedImage>,
+    pub icon: Option<EntryIcon>,
     pub label: String,
+    /// Secondary text, displayed under the label (e.g. the full path of a file).
+    pub detail: Option<String>,
+    /// Passed to the searcher on execution; if None, the label is passed.
     pub value: Option<String>,
-    // This is wasteful, as entries are sent in batch; additionally, the App current search id is enough,
-    // however, it's more solid to perfom the check at individual entry level, since it's much more
-    // solid, because operations doesn't require underlying assumptions.
-    // For example, when an entry is executed, there's no need to check for a search_id, because new
-    // searches clear the current browser list. However, this assumption introduces a dependency. By
-    // storing the search id here, we don't need to care about how the app behaves.
+    /// Byte ranges of the label that match the pattern, sorted and non-overlapping; frontends may
+    /// highlight them.
+    pub match_ranges: Vec<Range<usize>>,
+    /// Id of the search that produced the entry; the entries of previous searches are discarded.
+    ///
+    /// This is wasteful, as entries are sent in batch; additionally, the App current search id is
+    /// enough, however, it's more solid to perfom the check at individual entry level, since it's much
+    /// more solid, because operations doesn't require underlying assumptions.
+    /// For example, when an entry is executed, there's no need to check for a search_id, because new
+    /// searches clear the current browser list. However, this assumption introduces a dependency. By
+    /// storing the search id here, we don't need to care about how the app behaves.
     pub search_id: u32,
-    // Invalid entries are not executed; they are used to convey messages to the user.
+    /// Invalid entries are not executed; they are used to convey messages to the user.
     pub valid: bool,
 }
 
 impl SearchResultEntry {
     pub fn new(
-        icon: Option<SharedImage>,
+        icon: Option<EntryIcon>,
         label: String,
         value: Option<String>,
         search_id: u32,
@@ -27,9 +49,48 @@ impl SearchResultEntry {
         Self {
             icon,
             label,
+            detail: None,
             value,
+            match_ranges: vec![],
             search_id,
             valid,
         }
     }
+
+    pub fn with_detail(mut self, detail: String) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+
+    pub fn with_match_ranges(mut self, match_ranges: Vec<Range<usize>>) -> Self {
+        self.match_ranges = match_ranges;
+        self
+    }
+
+    /// Splits the label into consecutive segments, flagged as matching or not. Invalid ranges (e.g.
+    /// not on char boundaries) are ignored.
+    pub fn label_segments(&self) -> Vec<(&str, bool)> {
+        let mut segments = vec![];
+        let mut position = 0;
+
+        for range in &self.match_ranges {
+            if range.is_empty() || range.start < position || self.label.get(range.clone()).is_none()
+            {
+                continue;
+            }
+
+            if range.start > position {
+                segments.push((&self.label[position..range.start], false));
+            }
+
+            segments.push((&self.label[range.clone()], true));
+            position = range.end;
+        }
+
+        if position < self.label.len() {
+            segments.push((&self.label[position..], false));
+        }
+
+        segments
+    }
 }
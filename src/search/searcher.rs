@@ -1,28 +1,75 @@
-use fltk::app::Sender;
+use std::{path::PathBuf, sync::Arc};
 
-use crate::gui::message_event::MessageEvent;
+use super::{
+    action::Action, execute_error::ExecuteError, result_sink::ResultSink,
+    search_result_entry::SearchResultEntry,
+};
 
+/// A source of search results, selected by the [`SearchManager`](super::search_manager::SearchManager)
+/// according to the pattern (typically, its prefix).
 pub trait Searcher {
+    /// Returns true if the searcher handles the pattern.
     fn handles(&self, pattern: &str) -> bool;
 
-    // The implementation must take care of not running on empty patterns. This is because "empty
-    // pattern" is different from "empty string".
-    //
-    // Can run in a separate thread or not, but in the latter case, the search **must** be so fast that
-    // it's immediate from a user perspective.
-    //
-    fn search(&mut self, pattern: String, sender: Sender<MessageEvent>, search_id: u32);
-
-    fn execute(&self, value: String);
-
-    // Alternate execute mode, activated by Shift+Enter; optional.
-    // Returns true if supported; false otherwise.
-    //
-    fn alt_execute(&self, _value: String) -> bool {
-        false
+    /// Sends the results to the sink, tagged with `search_id`, in one or more batches.
+    ///
+    /// The implementation must take care of not running on empty patterns. This is because "empty
+    /// pattern" is different from "empty string".
+    ///
+    /// Can run in a separate thread or not, but in the latter case, the search **must** be so fast
+    /// that it's immediate from a user perspective.
+    fn search(&mut self, pattern: String, result_sink: Arc<dyn ResultSink>, search_id: u32);
+
+    /// Executes the value of an entry (e.g. opens the file, or outputs the text); errors are meant to
+    /// be displayed to the user. On success, the frontend is expected to exit, or to hide (when
+    /// resident), so that the focus goes back to the previous window.
+    fn execute(&self, value: String) -> Result<(), ExecuteError>;
+
+    /// Alternate execute mode, activated by Shift+Enter; optional.
+    /// Returns true if supported; false otherwise.
+    fn alt_execute(&self, _value: String) -> Result<bool, ExecuteError> {
+        Ok(false)
+    }
+
+    /// Executes the values of multiple selected entries; by default, they're executed one by one,
+    /// stopping at the first error.
+    fn execute_batch(&self, values: Vec<String>) -> Result<(), ExecuteError> {
+        values.into_iter().try_for_each(|value| self.execute(value))
+    }
+
+    /// Like [`Self::execute_batch`], in the alternate mode; by default, the values are executed one by
+    /// one. Returns true if supported; false otherwise.
+    fn alt_execute_batch(&self, values: Vec<String>) -> Result<bool, ExecuteError> {
+        for value in values {
+            if !self.alt_execute(value)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Secondary actions available for the entry (e.g. "copy path"); optional.
+    fn actions(&self, _entry: &SearchResultEntry) -> Vec<Action> {
+        vec![]
+    }
+
+    /// Performs one of the actions returned by [`Self::actions`]; `input` is set if the action
+    /// requires it.
+    fn execute_action(
+        &self,
+        _action_id: &str,
+        _value: String,
+        _input: Option<String>,
+    ) -> Result<(), ExecuteError> {
+        Ok(())
+    }
+
+    /// File to preview for the entry, if the entry represents one; optional.
+    fn preview_path(&self, _entry: &SearchResultEntry) -> Option<PathBuf> {
+        None
     }
 
-    // Implemented only when there is a separate thread.
-    //
+    /// Stops the search in progress; implemented only when there is a separate thread.
     fn stop(&mut self) {}
 }
@@ -0,0 +1,81 @@
+// Each searcher declares its own settings type, deserialized from the `[searchers.<name>]` section of
+// the configuration, via the `searcher_settings!` macro.
+//
+// The common settings are declared by the macro, rather than in a shared struct flattened via serde,
+// since flattening prevents the detection of unknown keys.
+
+// Generates the settings type, with the common settings, plus the searcher-specific ones, each with its
+// default value:
+//
+//     searcher_settings! {
+//         pub struct MySearcherSettings {
+//             default_prefix: "!",
+//             my_key: bool = false,
+//         }
+//     }
+//
+macro_rules! searcher_settings {
+    (
+        pub struct $name:ident {
+            default_prefix: $default_prefix:expr,
+            $( $(#[$field_meta:meta])* $field:ident: $field_type:ty = $field_default:expr, )*
+        }
+    ) => {
+        #[derive(Clone, serde::Deserialize)]
+        #[serde(default)]
+        pub struct $name {
+            pub enabled: bool,
+            /// Patterns starting with the prefix are handled by the searcher; the prefix is stripped
+            /// before searching. When the searcher is forced (via command line), the prefix is
+            /// optional.
+            pub prefix: String,
+            /// Maximum number of results displayed; None: no limit.
+            pub result_limit: Option<usize>,
+            pub output_mode: $crate::helpers::output_management::OutputMode,
+            pub clipboard_selection: $crate::helpers::clipboard_management::ClipboardSelection,
+            $( $(#[$field_meta])* pub $field: $field_type, )*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    enabled: true,
+                    prefix: $default_prefix.to_string(),
+                    result_limit: None,
+                    output_mode: Default::default(),
+                    clipboard_selection: Default::default(),
+                    $( $field: $field_default, )*
+                }
+            }
+        }
+
+        impl $name {
+            // Returns None if the pattern doesn't start with the prefix.
+            //
+            pub(crate) fn strip_prefix<'a>(&self, pattern: &'a str) -> Option<&'a str> {
+                pattern.strip_prefix(self.prefix.as_str())
+            }
+
+            pub(crate) fn limit_results<T>(&self, mut results: Vec<T>) -> Vec<T> {
+                if let Some(result_limit) = self.result_limit {
+                    results.truncate(result_limit);
+                }
+                results
+            }
+
+            pub(crate) fn output_settings(
+                &self,
+                config: &$crate::config::config_manager::Config,
+            ) -> $crate::helpers::output_management::OutputSettings {
+                $crate::helpers::output_management::OutputSettings {
+                    mode: self.output_mode,
+                    selection: self.clipboard_selection,
+                    restore_clipboard: config.restore_clipboard,
+                    clipboard_backend: config.clipboard_backend,
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use searcher_settings;
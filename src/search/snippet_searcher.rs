@@ -0,0 +1,436 @@
+use std::{fs, path::Path, sync::Arc};
+
+use chrono::Local;
+use serde::Deserialize;
+
+use super::{
+    execute_error::ExecuteError, result_sink::ResultSink, search_result_entry::SearchResultEntry,
+    searcher::Searcher, searcher_settings::searcher_settings,
+};
+use crate::{
+    config::config_manager::Config,
+    helpers::{
+        clipboard_management::{copy_to_clipboard, read_from_clipboard, ClipboardError},
+        output_management::{output_text, OutputSettings},
+    },
+};
+
+// The store is a directory, which can contain:
+//
+// - TOML files, with a `[[snippets]]` array of tables (keys: `name`, `text`, and optional `tags`);
+// - any other file, whose stem is the snippet name, and whose content is the text.
+//
+// Hidden files are ignored.
+//
+const SNIPPETS_DIR_BASENAME: &str = ".pm-spotlight-snippets";
+const HOME_DIR_NOT_FOUND_MESSAGE: &str = "Home directory not found; the snippets can't be loaded";
+
+searcher_settings! {
+    pub struct SnippetSearcherSettings {
+        default_prefix: ";",
+        /// Include the bundled kaomojis.
+        kaomojis: bool = true,
+    }
+}
+
+const DATE_PLACEHOLDER: &str = "{date}";
+const CLIPBOARD_PLACEHOLDER: &str = "{clipboard}";
+const CURSOR_PLACEHOLDER: &str = "{cursor}";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Maximum number of chars of the text displayed in the label.
+//
+const LABEL_TEXT_PREVIEW_CHARS: usize = 40;
+
+const KAOMOJI_TAG: &str = "kaomoji";
+
+// Format: (name, tags, text).
+//
+#[rustfmt::skip]
+const KAOMOJIS: &[(&str, &[&str], &str)] = &[
+    ("angry",      &["mad", "rage"],                  "(╬ Ò﹏Ó)"),
+    ("bear",       &["animal"],                       "ʕ•ᴥ•ʔ"),
+    ("cat",        &["animal", "meow"],               "(=^･ω･^=)"),
+    ("cry",        &["sad", "tears"],                 "(╥﹏╥)"),
+    ("dance",      &["party", "happy"],               "┏(・o･)┛♪┗ (･o･) ┓♪"),
+    ("disapprove", &["look", "stare"],                "ಠ_ಠ"),
+    ("excited",    &["happy", "yay"],                 "(ﾉ◕ヮ◕)ﾉ*:･ﾟ✧"),
+    ("flex",       &["strong", "muscle"],             "ᕙ(⇀‸↼‶)ᕗ"),
+    ("happy",      &["smile", "joy"],                 "(◕‿◕)"),
+    ("hug",        &["love", "embrace"],              "(づ｡◕‿‿◕｡)づ"),
+    ("lenny",      &["smirk"],                        "( ͡° ͜ʖ ͡°)"),
+    ("love",       &["heart"],                        "(♥ω♥*)"),
+    ("shrug",      &["whatever", "dunno"],            r"¯\_(ツ)_/¯"),
+    ("sleepy",     &["tired", "zzz"],                 "(－_－) zzZ"),
+    ("sunglasses", &["cool", "deal with it"],         "(•_•) ( •_•)>⌐■-■ (⌐■_■)"),
+    ("surprised",  &["shock", "wow"],                 "(⊙_☉)"),
+    ("tableflip",  &["flip", "rage"],                 "(╯°□°)╯︵ ┻━┻"),
+    ("tableback",  &["unflip", "calm"],               "┬─┬ノ( º _ ºノ)"),
+    ("wave",       &["hello", "bye", "greeting"],     "(・ω・)ノ"),
+    ("wink",       &["flirt"],                        "(^_-)"),
+];
+
+#[derive(Deserialize)]
+struct SnippetsFile {
+    snippets: Vec<Snippet>,
+}
+
+#[derive(Deserialize)]
+struct Snippet {
+    name: String,
+    text: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+pub struct SnippetSearcher {
+    settings: SnippetSearcherSettings,
+    output_settings: OutputSettings,
+}
+
+impl SnippetSearcher {
+    pub fn new(config: &Config) -> Self {
+        let settings = config.searchers.snippets.clone();
+        let output_settings = settings.output_settings(config);
+
+        Self {
+            settings,
+            output_settings,
+        }
+    }
+
+    // Returns the snippets, and the errors found while loading, which are displayed to the user.
+    //
+    fn load_snippets(&self) -> (Vec<Snippet>, Vec<String>) {
+        let bundled_kaomojis = if self.settings.kaomojis {
+            KAOMOJIS
+        } else {
+            &[]
+        };
+
+        let mut snippets = bundled_kaomojis
+            .iter()
+            .map(|(name, tags, text)| Snippet {
+                name: name.to_string(),
+                text: text.to_string(),
+                tags: tags
+                    .iter()
+                    .chain(&[KAOMOJI_TAG])
+                    .map(|tag| tag.to_string())
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut errors = vec![];
+
+        // Without a home directory, the store can't be located; the bundled kaomojis are still listed.
+        //
+        let Some(home_dir) = dirs::home_dir() else {
+            errors.push(HOME_DIR_NOT_FOUND_MESSAGE.to_string());
+            return (snippets, errors);
+        };
+
+        let snippets_dir = home_dir.join(SNIPPETS_DIR_BASENAME);
+
+        // A missing store is legitimate; only the bundled kaomojis (if any) are available in this case.
+        //
+        let Ok(dir_entries) = fs::read_dir(snippets_dir) else {
+            return (snippets, errors);
+        };
+
+        let mut paths = dir_entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .filter(|path| {
+                !path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with('.'))
+            })
+            .collect::<Vec<_>>();
+
+        paths.sort();
+
+        for path in paths {
+            match Self::load_snippets_file(&path) {
+                Ok(file_snippets) => snippets.extend(file_snippets),
+                Err(error) => errors.push(format!("{}: {}", path.display(), error)),
+            }
+        }
+
+        (snippets, errors)
+    }
+
+    fn load_snippets_file(path: &Path) -> Result<Vec<Snippet>, String> {
+        let content = fs::read_to_string(path).map_err(|error| error.to_string())?;
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            let snippets_file: SnippetsFile =
+                toml::from_str(&content).map_err(|error| error.message().to_string())?;
+
+            Ok(snippets_file.snippets)
+        } else {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+
+            // Editors typically add a trailing newline, which is not intended to be part of the text.
+            //
+            let text = content.strip_suffix('\n').unwrap_or(&content).to_string();
+
+            Ok(vec![Snippet {
+                name,
+                text,
+                tags: vec![],
+            }])
+        }
+    }
+
+    fn matches(snippet: &Snippet, pattern: &str) -> bool {
+        snippet.name.to_lowercase().contains(pattern)
+            || snippet
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(pattern))
+    }
+
+    fn build_label(snippet: &Snippet) -> String {
+        let first_line = snippet.text.lines().next().unwrap_or_default();
+        let mut preview = first_line
+            .chars()
+            .take(LABEL_TEXT_PREVIEW_CHARS)
+            .collect::<String>();
+
+        if preview != snippet.text {
+            preview.push('…');
+        }
+
+        format!("{}: {}", snippet.name, preview)
+    }
+
+    // Returns the expanded text, and the cursor position, as number of chars from the end of the text.
+    //
+    // The text is split at the cursor before expanding the other placeholders, so that a marker in the
+    // expanded values (e.g. in the clipboard) is not interpreted. Only the first cursor marker is
+    // meaningful; the others are dropped.
+    //
+    fn expand_placeholders(&self, text: &str) -> Result<(String, usize), ClipboardError> {
+        let (before_cursor, after_cursor) =
+            text.split_once(CURSOR_PLACEHOLDER).unwrap_or((text, ""));
+
+        let before_cursor = self.expand_value_placeholders(before_cursor)?;
+        let after_cursor =
+            self.expand_value_placeholders(&after_cursor.replace(CURSOR_PLACEHOLDER, ""))?;
+
+        let cursor_offset = after_cursor.chars().count();
+
+        Ok((before_cursor + &after_cursor, cursor_offset))
+    }
+
+    fn expand_value_placeholders(&self, text: &str) -> Result<String, ClipboardError> {
+        let mut text = text.to_string();
+
+        if text.contains(DATE_PLACEHOLDER) {
+            let date = Local::now().format(DATE_FORMAT).to_string();
+            text = text.replace(DATE_PLACEHOLDER, &date);
+        }
+
+        if text.contains(CLIPBOARD_PLACEHOLDER) {
+            let clipboard = read_from_clipboard(self.output_settings.clipboard_backend)?;
+            text = text.replace(CLIPBOARD_PLACEHOLDER, &clipboard);
+        }
+
+        Ok(text)
+    }
+}
+
+impl Searcher for SnippetSearcher {
+    fn handles(&self, pattern: &str) -> bool {
+        self.settings.strip_prefix(pattern).is_some()
+    }
+
+    // Differently from the emoji searcher, an empty pattern lists all the snippets, since the names are
+    // not as predictable.
+    //
+    fn search(&mut self, pattern: String, result_sink: Arc<dyn ResultSink>, search_id: u32) {
+        let pattern = self
+            .settings
+            .strip_prefix(&pattern)
+            .unwrap_or(&pattern)
+            .to_lowercase();
+
+        let (snippets, errors) = self.load_snippets();
+
+        let error_entries = errors
+            .into_iter()
+            .map(|error| SearchResultEntry::new(None, error, None, search_id, false));
+
+        let snippet_entries = snippets
+            .iter()
+            .filter(|snippet| Self::matches(snippet, &pattern))
+            .map(|snippet| {
+                SearchResultEntry::new(
+                    None,
+                    Self::build_label(snippet),
+                    Some(snippet.text.clone()),
+                    search_id,
+                    true,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let search_result = error_entries
+            .chain(self.settings.limit_results(snippet_entries))
+            .collect();
+
+        result_sink.update_list(search_result);
+    }
+
+    fn execute(&self, text: String) -> Result<(), ExecuteError> {
+        let (text, cursor_offset) = self.expand_placeholders(&text)?;
+
+        output_text(text, cursor_offset, self.output_settings)?;
+
+        Ok(())
+    }
+
+    // Multiple snippets are always copied, one per line, regardless of the output mode; the cursor
+    // placeholders are therefore ignored.
+    //
+    fn execute_batch(&self, texts: Vec<String>) -> Result<(), ExecuteError> {
+        let texts = texts
+            .iter()
+            .map(|text| self.expand_placeholders(text).map(|(text, _)| text))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        copy_to_clipboard(
+            texts.join("\n"),
+            self.output_settings.selection,
+            None,
+            self.output_settings.clipboard_backend,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf, process};
+
+    use super::*;
+    use crate::search::result_sink::tests::RecordingSink;
+
+    fn snippet(name: &str, text: &str, tags: &[&str]) -> Snippet {
+        Snippet {
+            name: name.to_string(),
+            text: text.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    // Writes the file in a directory specific to the test, and returns its path.
+    //
+    fn write_test_file(test_name: &str, basename: &str, content: &str) -> PathBuf {
+        let test_dir =
+            env::temp_dir().join(format!("pm-spotlight-test-{}-{test_name}", process::id()));
+
+        fs::create_dir_all(&test_dir).unwrap();
+
+        let path = test_dir.join(basename);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn matches_name_or_tags() {
+        let snippet = snippet("Signature", "Regards", &["Mail"]);
+
+        assert!(SnippetSearcher::matches(&snippet, "sign"));
+        assert!(SnippetSearcher::matches(&snippet, "mail"));
+        assert!(!SnippetSearcher::matches(&snippet, "regards"));
+    }
+
+    #[test]
+    fn label_previews_the_first_line() {
+        let short = snippet("sig", "Regards", &[]);
+        let multiline = snippet("sig", "Regards\nMe", &[]);
+        let long = snippet("lorem", &"a".repeat(LABEL_TEXT_PREVIEW_CHARS + 1), &[]);
+
+        assert_eq!(SnippetSearcher::build_label(&short), "sig: Regards");
+        assert_eq!(SnippetSearcher::build_label(&multiline), "sig: Regards…");
+        assert_eq!(
+            SnippetSearcher::build_label(&long),
+            format!("lorem: {}…", "a".repeat(LABEL_TEXT_PREVIEW_CHARS))
+        );
+    }
+
+    #[test]
+    fn cursor_offset_is_counted_in_chars_from_the_end() {
+        let searcher = SnippetSearcher::new(&Config::default());
+
+        let (text, cursor_offset) = searcher
+            .expand_placeholders("<b>{cursor}</b>{cursor}…")
+            .unwrap();
+
+        assert_eq!(text, "<b></b>…");
+        assert_eq!(cursor_offset, 5);
+    }
+
+    #[test]
+    fn loads_toml_files() {
+        let path = write_test_file(
+            "loads_toml_files",
+            "mail.toml",
+            "[[snippets]]\nname = \"sig\"\ntext = \"Regards\"\ntags = [\"mail\"]\n",
+        );
+
+        let snippets = SnippetSearcher::load_snippets_file(&path).unwrap();
+
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].name, "sig");
+        assert_eq!(snippets[0].text, "Regards");
+        assert_eq!(snippets[0].tags, vec!["mail"]);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn loads_plain_files_without_the_trailing_newline() {
+        let path = write_test_file("loads_plain_files", "sig.txt", "Regards\n");
+
+        let snippets = SnippetSearcher::load_snippets_file(&path).unwrap();
+
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].name, "sig");
+        assert_eq!(snippets[0].text, "Regards");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn invalid_toml_files_are_reported() {
+        let path = write_test_file("invalid_toml_files", "broken.toml", "[[snippets]]\n");
+
+        assert!(SnippetSearcher::load_snippets_file(&path).is_err());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn bundled_kaomojis_can_be_disabled() {
+        let mut config = Config::default();
+
+        for (kaomojis, expected_found) in [(true, true), (false, false)] {
+            config.searchers.snippets.kaomojis = kaomojis;
+
+            let sink = Arc::new(RecordingSink::default());
+            SnippetSearcher::new(&config).search(";tableflip".to_string(), sink.clone(), 1);
+
+            let found = sink.values().contains(&Some("(╯°□°)╯︵ ┻━┻".to_string()));
+
+            assert_eq!(found, expected_found);
+        }
+    }
+}
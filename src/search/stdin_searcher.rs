@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use super::{
+    execute_error::ExecuteError, result_sink::ResultSink, search_result_entry::SearchResultEntry,
+    searcher::Searcher,
+};
+
+// Searches the items read from stdin, and prints the selection, in the style of `dmenu`.
+//
+// Items match if they contain the pattern chars in order (case-insensitive); substring matches are
+// displayed first, then the others by match span length. Ties keep the input order.
+//
+const RANK_SUBSTRING: u8 = 0;
+const RANK_SUBSEQUENCE: u8 = 1;
+
+pub struct StdinSearcher {
+    // Shared, since a searcher is instantiated on each search.
+    items: Arc<Vec<String>>,
+    last_pattern: String,
+}
+
+impl StdinSearcher {
+    pub fn new(items: Arc<Vec<String>>) -> Self {
+        Self {
+            items,
+            last_pattern: String::new(),
+        }
+    }
+
+    // Returns (rank, span length), or None if the item doesn't match.
+    //
+    fn match_rank(item: &str, pattern: &[char]) -> Option<(u8, usize)> {
+        let item = item.to_lowercase().chars().collect::<Vec<_>>();
+
+        if pattern.is_empty() {
+            return Some((RANK_SUBSTRING, 0));
+        }
+
+        if item.windows(pattern.len()).any(|window| window == pattern) {
+            return Some((RANK_SUBSTRING, pattern.len()));
+        }
+
+        // Shortest span among the ones starting at each occurrence of the first pattern char.
+        //
+        (0..item.len())
+            .filter(|start| item[*start] == pattern[0])
+            .filter_map(|start| {
+                let mut pattern_chars = pattern.iter().peekable();
+
+                for (index, item_char) in item.iter().enumerate().skip(start) {
+                    if pattern_chars
+                        .next_if(|pattern_char| *pattern_char == item_char)
+                        .is_some()
+                        && pattern_chars.peek().is_none()
+                    {
+                        return Some(index - start + 1);
+                    }
+                }
+
+                None
+            })
+            .min()
+            .map(|span| (RANK_SUBSEQUENCE, span))
+    }
+}
+
+impl Searcher for StdinSearcher {
+    fn handles(&self, _pattern: &str) -> bool {
+        true
+    }
+
+    // An empty pattern lists all the items, like dmenu.
+    //
+    fn search(&mut self, pattern: String, result_sink: Arc<dyn ResultSink>, search_id: u32) {
+        let pattern_chars = pattern.to_lowercase().chars().collect::<Vec<_>>();
+
+        let mut ranked_items = self
+            .items
+            .iter()
+            .filter_map(|item| Self::match_rank(item, &pattern_chars).map(|rank| (rank, item)))
+            .collect::<Vec<_>>();
+
+        // The sort is stable, so the input order is kept for equal ranks.
+        //
+        ranked_items.sort_by_key(|(rank, _)| *rank);
+
+        let mut search_result = ranked_items
+            .into_iter()
+            .map(|(_, item)| SearchResultEntry::new(None, item.clone(), None, search_id, true))
+            .collect::<Vec<_>>();
+
+        // Like dmenu, if nothing matches, the typed text can be selected.
+        //
+        if search_result.is_empty() && !pattern.is_empty() {
+            search_result.push(SearchResultEntry::new(
+                None,
+                pattern.clone(),
+                None,
+                search_id,
+                true,
+            ));
+        }
+
+        self.last_pattern = pattern;
+
+        result_sink.update_list(search_result);
+    }
+
+    fn execute(&self, item: String) -> Result<(), ExecuteError> {
+        println!("{item}");
+
+        Ok(())
+    }
+
+    // Prints the typed text, rather than the selected item, like dmenu's Shift+Enter.
+    //
+    fn alt_execute(&self, _item: String) -> Result<bool, ExecuteError> {
+        println!("{}", self.last_pattern);
+
+        Ok(true)
+    }
+
+    // The typed text is printed once, regardless of the number of selected items.
+    //
+    fn alt_execute_batch(&self, _items: Vec<String>) -> Result<bool, ExecuteError> {
+        println!("{}", self.last_pattern);
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,439 @@
+// Terminal frontend, for usage without a display server (e.g. over SSH); it follows the same message
+// flow as the GUI.
+//
+// The interface is drawn on stderr, so that stdout can be used for the output (print and dmenu modes).
+//
+// Keys: typing edits the pattern (Ctrl+U clears it); Up/Down/PageUp/PageDown move the selection; Enter
+// executes the selected entry, and Shift+Enter the alternate execution (Alt+Enter is accepted as well,
+// since most terminals don't report Shift); Tab cycles the scopes; Esc/Ctrl+C cancel.
+
+use std::{
+    io::{self, Stderr},
+    panic, process,
+    sync::{mpsc, Arc},
+    time::Duration,
+};
+
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+
+#[cfg(target_os = "linux")]
+use pm_spotlight::config::config_watcher::watch_configuration;
+use pm_spotlight::{
+    config::config_manager::ConfigManager,
+    search::{search_manager::SearchManager, search_result_entry::SearchResultEntry},
+};
+
+use crate::{
+    events::{
+        message_event::MessageEvent::{self, *},
+        message_sender::{MessageSender, MessageSink},
+    },
+    frontend::{Frontend, CANCEL_EXIT_STATUS},
+};
+
+const TITLE: &str = "Poor Man's Spotlight!";
+
+// Interval for checking the messages, while waiting for keys.
+//
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+const PAGE_SIZE: isize = 10;
+
+type TuiTerminal = Terminal<CrosstermBackend<Stderr>>;
+
+pub struct PMSpotlightTui {
+    search_manager: SearchManager,
+    config_manager: ConfigManager,
+    // Write the selected entry value to stdout, instead of executing it.
+    print_selection: bool,
+    current_search_id: u32,
+    sender: mpsc::Sender<MessageEvent>,
+    receiver: mpsc::Receiver<MessageEvent>,
+    terminal: TuiTerminal,
+    input: String,
+    entries: Vec<SearchResultEntry>,
+    list_state: ListState,
+}
+
+impl PMSpotlightTui {
+    pub fn build(
+        search_manager: SearchManager,
+        config_manager: ConfigManager,
+        print_selection: bool,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        #[cfg(target_os = "linux")]
+        {
+            let (change_sender, error_sender) = (sender.clone(), sender.clone());
+            watch_configuration(
+                config_manager.clone(),
+                move || MessageSender::send(&change_sender, ReloadConfiguration),
+                move |error| {
+                    MessageSender::send(
+                        &error_sender,
+                        DisplayError(format!(
+                            "Configuration watcher error (changes won't be reloaded): {error}"
+                        )),
+                    )
+                },
+            );
+        }
+
+        // Otherwise, a panic leaves the terminal in raw mode.
+        //
+        let default_panic_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |panic_info| {
+            Self::restore_terminal();
+            default_panic_hook(panic_info);
+        }));
+
+        Self {
+            search_manager,
+            config_manager,
+            print_selection,
+            current_search_id: 0,
+            sender,
+            receiver,
+            terminal: Self::init_terminal(),
+            input: String::new(),
+            entries: vec![],
+            list_state: ListState::default(),
+        }
+    }
+
+    /***************************************************************************
+     * Terminal event handlers
+     ***************************************************************************/
+
+    fn terminal_event_key(&mut self, key_event: KeyEvent) {
+        if key_event.kind != KeyEventKind::Press {
+            return;
+        }
+
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key_event.code {
+            KeyCode::Esc => self.cancel(),
+            KeyCode::Char('c') if ctrl => self.cancel(),
+            KeyCode::Char('u') if ctrl => {
+                self.input.clear();
+                self.start_search();
+            }
+            KeyCode::Char(char) if !ctrl => {
+                self.input.push(char);
+                self.start_search();
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.start_search();
+            }
+            KeyCode::Enter => {
+                let alternate = key_event
+                    .modifiers
+                    .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT);
+                self.send(ExecuteEntry(alternate));
+            }
+            KeyCode::Tab => self.send(CycleScope),
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-PAGE_SIZE),
+            KeyCode::PageDown => self.move_selection(PAGE_SIZE),
+            _ => {}
+        }
+    }
+
+    /***************************************************************************
+     * MessageEvent handlers
+     ***************************************************************************/
+
+    fn message_event_start_search(&mut self, pattern: String) {
+        self.entries.clear();
+        self.list_state.select(None);
+        self.current_search_id = self
+            .search_manager
+            .search(pattern, Arc::new(MessageSink(self.sender.clone())));
+    }
+
+    fn message_event_update_list(&mut self, entries: Vec<SearchResultEntry>) {
+        self.entries.extend(
+            entries
+                .into_iter()
+                .filter(|entry| entry.search_id == self.current_search_id),
+        );
+
+        if self.list_state.selected().is_none() && !self.entries.is_empty() {
+            self.list_state.select(Some(0));
+        }
+    }
+
+    fn message_event_execute_entry(&mut self, alternate: bool) {
+        let Some(entry) = self
+            .list_state
+            .selected()
+            .and_then(|index| self.entries.get(index))
+            .cloned()
+        else {
+            return;
+        };
+
+        if self.current_search_id != entry.search_id || !entry.valid {
+            return;
+        }
+
+        let entry_value = entry.value.unwrap_or(entry.label);
+
+        // The searchers may replace the process, or print, so the terminal is restored before
+        // executing.
+        //
+        Self::restore_terminal();
+
+        if self.print_selection {
+            println!("{entry_value}");
+            process::exit(0);
+        }
+
+        let execute_result = if alternate {
+            match self.search_manager.alt_execute(entry_value) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err("Alternate execution is not supported".to_string()),
+                Err(error) => Err(error.to_string()),
+            }
+        } else {
+            self.search_manager
+                .execute(entry_value)
+                .map_err(|error| error.to_string())
+        };
+
+        if let Err(error) = execute_result {
+            self.terminal = Self::init_terminal();
+            self.display_error(error);
+        } else {
+            process::exit(0);
+        }
+    }
+
+    // On error, the current configuration is kept.
+    //
+    fn message_event_reload_configuration(&mut self) {
+        match self.config_manager.load_configuration() {
+            Ok(config) => {
+                let warnings = config.warnings.clone();
+
+                self.search_manager.update_config(config);
+
+                if warnings.is_empty() {
+                    self.start_search();
+                } else {
+                    self.display_errors(warnings);
+                }
+            }
+            Err(error) => self.display_error(error.to_string()),
+        }
+    }
+
+    fn message_event_cycle_scope(&mut self) {
+        self.search_manager.cycle_scope();
+        self.start_search();
+    }
+
+    /***************************************************************************
+     * Helpers
+     ***************************************************************************/
+
+    fn display_error(&mut self, message: String) {
+        self.display_errors(vec![message]);
+    }
+
+    fn send(&self, event: MessageEvent) {
+        MessageSender::send(&self.sender, event);
+    }
+
+    fn cancel(&self) {
+        Self::restore_terminal();
+        process::exit(CANCEL_EXIT_STATUS);
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let current_index = self.list_state.selected().unwrap_or_default() as isize;
+        let last_index = self.entries.len() as isize - 1;
+
+        self.list_state
+            .select(Some((current_index + offset).clamp(0, last_index) as usize));
+    }
+
+    fn draw(&mut self) {
+        let title = match self.search_manager.active_scope() {
+            Some(scope) => format!("{TITLE} [@{scope}]"),
+            None => TITLE.to_string(),
+        };
+
+        self.terminal
+            .draw(|frame| {
+                Self::render(
+                    frame,
+                    &title,
+                    &self.input,
+                    &self.entries,
+                    &mut self.list_state,
+                )
+            })
+            .unwrap();
+    }
+
+    fn render(
+        frame: &mut Frame,
+        title: &str,
+        input: &str,
+        entries: &[SearchResultEntry],
+        list_state: &mut ListState,
+    ) {
+        let [input_area, list_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(input).block(Block::bordered().title(title)),
+            input_area,
+        );
+
+        let list_items = entries.iter().map(|entry| {
+            let style = if entry.valid {
+                Style::default()
+            } else {
+                Style::default().add_modifier(Modifier::DIM)
+            };
+
+            // The parts of the label that match the pattern are highlighted.
+            //
+            let spans = entry
+                .label_segments()
+                .into_iter()
+                .map(|(segment, matching)| {
+                    if matching {
+                        Span::styled(segment, Style::default().add_modifier(Modifier::BOLD))
+                    } else {
+                        Span::raw(segment)
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            ListItem::new(Line::from(spans)).style(style)
+        });
+
+        let list = List::new(list_items)
+            .block(Block::bordered())
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(list, list_area, list_state);
+
+        frame.set_cursor_position((
+            input_area.x + 1 + input.chars().count() as u16,
+            input_area.y + 1,
+        ));
+    }
+
+    fn init_terminal() -> TuiTerminal {
+        enable_raw_mode().unwrap();
+        execute!(io::stderr(), EnterAlternateScreen).unwrap();
+
+        Terminal::new(CrosstermBackend::new(io::stderr())).unwrap()
+    }
+
+    // Errors are ignored, since this is invoked on exit, including panics.
+    //
+    fn restore_terminal() {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stderr(), LeaveAlternateScreen);
+    }
+}
+
+impl Frontend for PMSpotlightTui {
+    fn set_query(&mut self, query: &str) {
+        self.input = query.to_string();
+    }
+
+    fn start_search(&mut self) {
+        self.send(StartSearch(self.input.clone()));
+    }
+
+    fn display_errors(&mut self, messages: Vec<String>) {
+        self.entries = messages
+            .into_iter()
+            .map(|message| {
+                SearchResultEntry::new(None, message, None, self.current_search_id, false)
+            })
+            .collect();
+        self.list_state.select(None);
+    }
+
+    fn run(&mut self) {
+        loop {
+            self.draw();
+
+            if event::poll(POLL_INTERVAL).unwrap() {
+                if let Event::Key(key_event) = event::read().unwrap() {
+                    self.terminal_event_key(key_event);
+                }
+            }
+
+            while let Ok(event) = self.receiver.try_recv() {
+                match event {
+                    StartSearch(pattern) => {
+                        self.message_event_start_search(pattern);
+                    }
+                    UpdateList(entries) => {
+                        self.message_event_update_list(entries);
+                    }
+                    // The list is navigated directly from the input.
+                    //
+                    FocusOnBrowser => {}
+                    ExecuteEntry(alternate) => {
+                        self.message_event_execute_entry(alternate);
+                    }
+                    ReloadConfiguration => {
+                        self.message_event_reload_configuration();
+                    }
+                    DisplayError(message) => {
+                        self.display_error(message);
+                    }
+                    CycleScope => {
+                        self.message_event_cycle_scope();
+                    }
+                    // The TUI is not resident.
+                    //
+                    ShowWindow(_) | HideWindow | ToggleWindow => {}
+                    // Actions are supported only by the GUI.
+                    //
+                    OpenActions | ExecuteAction(_) => {}
+                    // The preview is supported only by the GUI.
+                    //
+                    UpdatePreview | DisplayPreview(..) => {}
+                }
+            }
+        }
+    }
+}
+
+impl MessageSender for mpsc::Sender<MessageEvent> {
+    // Fails only if the receiver has been dropped, which happens only on exit.
+    //
+    fn send(&self, event: MessageEvent) {
+        let _ = mpsc::Sender::send(self, event);
+    }
+}